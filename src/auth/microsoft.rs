@@ -0,0 +1,707 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Microsoft accounts
+//!
+//! The login is a chain of tokens: the Microsoft OAuth token (with the device code flow, the user
+//! enters a code on another device), the Xbox Live user token, the XSTS token, and finally the
+//! Minecraft access token. The Microsoft refresh token is kept, so the chain can be run again
+//! without the user, see [`MicrosoftAuth::refresh`].
+//!
+//! A client id of an Azure application is needed, see
+//! <https://learn.microsoft.com/entra/identity-platform/quickstart-register-app>.
+//!
+//! # Example
+//!
+//! ```
+//! use aml_core::auth::microsoft::MicrosoftAuth;
+//! use aml_core::launch::options::LaunchOptions;
+//!
+//! async fn fn_name(mut options: LaunchOptions) {
+//!     let auth = MicrosoftAuth::new("00000000-0000-0000-0000-000000000000");
+//!     let device_code = auth.request_device_code().await.unwrap();
+//!     println!("{}", device_code.message);
+//!     let account = auth.login(&device_code).await.unwrap();
+//!     options.set_account(account);
+//! }
+//! ```
+
+use std::{
+    fmt::Display,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    core::HTTP_CLIENT,
+    launch::options::{GameProfile, UserType},
+};
+
+use super::Account;
+
+/// The permissions asked from the user, `offline_access` is needed for the refresh token.
+const SCOPE: &str = "XboxLive.signin offline_access";
+
+/// The URLs of the services in the login chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct MicrosoftEndpoints {
+    pub device_code: String,
+    pub token: String,
+    pub xbox_user: String,
+    pub xsts: String,
+    pub minecraft_login: String,
+    pub entitlements: String,
+    pub profile: String,
+}
+
+impl Default for MicrosoftEndpoints {
+    fn default() -> Self {
+        Self {
+            device_code: "https://login.microsoftonline.com/consumers/oauth2/v2.0/devicecode"
+                .to_string(),
+            token: "https://login.microsoftonline.com/consumers/oauth2/v2.0/token".to_string(),
+            xbox_user: "https://user.auth.xboxlive.com/user/authenticate".to_string(),
+            xsts: "https://xsts.auth.xboxlive.com/xsts/authorize".to_string(),
+            minecraft_login: "https://api.minecraftservices.com/authentication/login_with_xbox"
+                .to_string(),
+            entitlements: "https://api.minecraftservices.com/entitlements/mcstore".to_string(),
+            profile: "https://api.minecraftservices.com/minecraft/profile".to_string(),
+        }
+    }
+}
+
+/// Why the login failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum MicrosoftAuthError {
+    /// The user declined the authorization.
+    AuthorizationDeclined,
+
+    /// The user didn't enter the code in time.
+    DeviceCodeExpired,
+
+    /// Another OAuth error, like an invalid or revoked refresh token.
+    OAuth { error: String, description: String },
+
+    /// The Microsoft account has no Xbox profile, it has to sign in on <https://www.xbox.com> once.
+    NoXboxAccount,
+
+    /// Xbox Live is not available in the country of the account.
+    XboxUnavailable,
+
+    /// The account needs adult verification on the Xbox page, in South Korea.
+    AdultVerificationRequired,
+
+    /// A child account, it has to be added to a Family by an adult.
+    ChildAccount,
+
+    /// Another XSTS error code.
+    Xsts(u64),
+
+    /// The account doesn't own Minecraft.
+    NoMinecraftLicense,
+
+    /// The account owns Minecraft, but hasn't created a profile (chosen a name) yet.
+    NoProfile,
+
+    /// A service answered with an unexpected status code.
+    Status { url: String, status: u16 },
+
+    /// The request failed or the response couldn't be read.
+    Network(String),
+}
+
+impl Display for MicrosoftAuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MicrosoftAuthError::AuthorizationDeclined => {
+                write!(f, "the authorization was declined")
+            }
+            MicrosoftAuthError::DeviceCodeExpired => write!(f, "the device code expired"),
+            MicrosoftAuthError::OAuth { error, description } => {
+                write!(f, "oauth error {error}: {description}")
+            }
+            MicrosoftAuthError::NoXboxAccount => {
+                write!(f, "the Microsoft account has no Xbox profile")
+            }
+            MicrosoftAuthError::XboxUnavailable => {
+                write!(
+                    f,
+                    "Xbox Live is not available in the country of the account"
+                )
+            }
+            MicrosoftAuthError::AdultVerificationRequired => {
+                write!(f, "the account needs adult verification")
+            }
+            MicrosoftAuthError::ChildAccount => {
+                write!(f, "a child account has to be added to a Family by an adult")
+            }
+            MicrosoftAuthError::Xsts(code) => write!(f, "xsts error {code}"),
+            MicrosoftAuthError::NoMinecraftLicense => {
+                write!(f, "the account doesn't own Minecraft")
+            }
+            MicrosoftAuthError::NoProfile => {
+                write!(f, "the account has no Minecraft profile yet")
+            }
+            MicrosoftAuthError::Status { url, status } => {
+                write!(f, "{url} responded with status {status}")
+            }
+            MicrosoftAuthError::Network(error) => write!(f, "network error: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for MicrosoftAuthError {}
+
+impl From<reqwest::Error> for MicrosoftAuthError {
+    fn from(value: reqwest::Error) -> Self {
+        MicrosoftAuthError::Network(value.to_string())
+    }
+}
+
+/// The code the user enters at `verification_uri`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+
+    /// Seconds until the code expires.
+    pub expires_in: u64,
+
+    /// Seconds to wait between polls.
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+
+    /// The instruction for the user, in the language of the system.
+    pub message: String,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+/// The Microsoft OAuth token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MicrosoftToken {
+    pub access_token: String,
+    pub refresh_token: String,
+
+    /// Unix time in seconds.
+    pub expires_at: u64,
+}
+
+/// A logged in Microsoft account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MicrosoftAccount {
+    pub profile: GameProfile,
+
+    /// The Minecraft access token.
+    pub access_token: String,
+
+    /// Unix time in seconds when the Minecraft access token expires.
+    pub expires_at: u64,
+
+    /// The Microsoft refresh token, see [`MicrosoftAuth::refresh`].
+    pub refresh_token: String,
+}
+
+impl MicrosoftAccount {
+    pub fn is_expired(&self) -> bool {
+        now() >= self.expires_at
+    }
+}
+
+impl From<MicrosoftAccount> for Account {
+    fn from(account: MicrosoftAccount) -> Self {
+        Self {
+            profile: account.profile,
+            access_token: account.access_token,
+            user_type: UserType::Msa,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OAuthToken {
+    access_token: String,
+    refresh_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct OAuthError {
+    error: String,
+    #[serde(default)]
+    error_description: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct XboxToken {
+    token: String,
+    display_claims: XboxDisplayClaims,
+}
+
+#[derive(Deserialize)]
+struct XboxDisplayClaims {
+    xui: Vec<XboxUserInfo>,
+}
+
+#[derive(Deserialize)]
+struct XboxUserInfo {
+    uhs: String,
+}
+
+#[derive(Deserialize)]
+struct XstsError {
+    #[serde(rename = "XErr")]
+    code: u64,
+}
+
+#[derive(Deserialize)]
+struct MinecraftToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct Entitlements {
+    #[serde(default)]
+    items: Vec<Entitlement>,
+}
+
+#[derive(Deserialize)]
+struct Entitlement {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct MinecraftProfile {
+    id: String,
+    name: String,
+}
+
+/// The Microsoft login chain
+#[derive(Debug, Clone)]
+pub struct MicrosoftAuth {
+    client_id: String,
+    endpoints: MicrosoftEndpoints,
+}
+
+impl MicrosoftAuth {
+    pub fn new(client_id: &str) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            endpoints: MicrosoftEndpoints::default(),
+        }
+    }
+
+    /// Use other URLs for the services, like a mock server.
+    pub fn endpoints(self, endpoints: MicrosoftEndpoints) -> Self {
+        Self { endpoints, ..self }
+    }
+
+    /// Start the device code flow, show [`DeviceCode::message`] to the user.
+    pub async fn request_device_code(&self) -> Result<DeviceCode, MicrosoftAuthError> {
+        let request = HTTP_CLIENT
+            .post(&self.endpoints.device_code)
+            .form(&[("client_id", self.client_id.as_str()), ("scope", SCOPE)]);
+        let response = send(request).await?;
+        if !response.status().is_success() {
+            return Err(oauth_error(response).await);
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Wait until the user entered the code, then get the Microsoft token.
+    pub async fn poll_device_code(
+        &self,
+        device_code: &DeviceCode,
+    ) -> Result<MicrosoftToken, MicrosoftAuthError> {
+        let mut interval = device_code.interval;
+        loop {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            let request = HTTP_CLIENT.post(&self.endpoints.token).form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+                ("client_id", self.client_id.as_str()),
+                ("device_code", device_code.device_code.as_str()),
+            ]);
+            let response = send(request).await?;
+            if response.status().is_success() {
+                return Ok(microsoft_token(response.json().await?));
+            }
+            match oauth_error(response).await {
+                MicrosoftAuthError::OAuth { error, .. } if error == "authorization_pending" => (),
+                MicrosoftAuthError::OAuth { error, .. } if error == "slow_down" => interval += 5,
+                error => return Err(error),
+            }
+        }
+    }
+
+    /// Get a new Microsoft token with the refresh token.
+    pub async fn refresh_token(
+        &self,
+        refresh_token: &str,
+    ) -> Result<MicrosoftToken, MicrosoftAuthError> {
+        let request = HTTP_CLIENT.post(&self.endpoints.token).form(&[
+            ("grant_type", "refresh_token"),
+            ("client_id", self.client_id.as_str()),
+            ("refresh_token", refresh_token),
+            ("scope", SCOPE),
+        ]);
+        let response = send(request).await?;
+        if !response.status().is_success() {
+            return Err(oauth_error(response).await);
+        }
+        Ok(microsoft_token(response.json().await?))
+    }
+
+    /// Wait for the device code, then log in to Minecraft.
+    pub async fn login(
+        &self,
+        device_code: &DeviceCode,
+    ) -> Result<MicrosoftAccount, MicrosoftAuthError> {
+        let token = self.poll_device_code(device_code).await?;
+        self.login_minecraft(&token).await
+    }
+
+    /// Log in again without the user, when the Minecraft access token expired.
+    pub async fn refresh(
+        &self,
+        account: &MicrosoftAccount,
+    ) -> Result<MicrosoftAccount, MicrosoftAuthError> {
+        let token = self.refresh_token(&account.refresh_token).await?;
+        self.login_minecraft(&token).await
+    }
+
+    /// The rest of the chain after the Microsoft token: Xbox Live, XSTS, Minecraft, the
+    /// entitlements and the profile.
+    pub async fn login_minecraft(
+        &self,
+        token: &MicrosoftToken,
+    ) -> Result<MicrosoftAccount, MicrosoftAuthError> {
+        let xbox_user: XboxToken =
+            json_response(HTTP_CLIENT.post(&self.endpoints.xbox_user).json(&json!({
+                "Properties": {
+                    "AuthMethod": "RPS",
+                    "SiteName": "user.auth.xboxlive.com",
+                    "RpsTicket": format!("d={}", token.access_token)
+                },
+                "RelyingParty": "http://auth.xboxlive.com",
+                "TokenType": "JWT"
+            })))
+            .await?;
+
+        let request = HTTP_CLIENT.post(&self.endpoints.xsts).json(&json!({
+            "Properties": {
+                "SandboxId": "RETAIL",
+                "UserTokens": [xbox_user.token]
+            },
+            "RelyingParty": "rp://api.minecraftservices.com/",
+            "TokenType": "JWT"
+        }));
+        let response = send(request).await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            let error: XstsError = response.json().await?;
+            return Err(match error.code {
+                2148916233 => MicrosoftAuthError::NoXboxAccount,
+                2148916235 => MicrosoftAuthError::XboxUnavailable,
+                2148916236 | 2148916237 => MicrosoftAuthError::AdultVerificationRequired,
+                2148916238 => MicrosoftAuthError::ChildAccount,
+                code => MicrosoftAuthError::Xsts(code),
+            });
+        }
+        let xsts: XboxToken = parse_response(response).await?;
+        let user_hash = xsts
+            .display_claims
+            .xui
+            .first()
+            .map(|info| info.uhs.clone())
+            .ok_or_else(|| MicrosoftAuthError::Network("no user hash in the XSTS token".into()))?;
+
+        let minecraft: MinecraftToken = json_response(
+            HTTP_CLIENT
+                .post(&self.endpoints.minecraft_login)
+                .json(&json!({
+                    "identityToken": format!("XBL3.0 x={user_hash};{}", xsts.token)
+                })),
+        )
+        .await?;
+        let bearer = format!("Bearer {}", minecraft.access_token);
+
+        let entitlements: Entitlements = json_response(
+            HTTP_CLIENT
+                .get(&self.endpoints.entitlements)
+                .header("Authorization", &bearer),
+        )
+        .await?;
+        let owns_minecraft = entitlements
+            .items
+            .iter()
+            .any(|item| item.name == "game_minecraft" || item.name == "product_minecraft");
+
+        // Game Pass accounts have a profile without the entitlements
+        let response = send(
+            HTTP_CLIENT
+                .get(&self.endpoints.profile)
+                .header("Authorization", &bearer),
+        )
+        .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(if owns_minecraft {
+                MicrosoftAuthError::NoProfile
+            } else {
+                MicrosoftAuthError::NoMinecraftLicense
+            });
+        }
+        let profile: MinecraftProfile = parse_response(response).await?;
+
+        Ok(MicrosoftAccount {
+            profile: GameProfile {
+                name: profile.name,
+                uuid: profile.id,
+            },
+            access_token: minecraft.access_token,
+            expires_at: now() + minecraft.expires_in,
+            refresh_token: token.refresh_token.clone(),
+        })
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn microsoft_token(token: OAuthToken) -> MicrosoftToken {
+    MicrosoftToken {
+        access_token: token.access_token,
+        refresh_token: token.refresh_token,
+        expires_at: now() + token.expires_in,
+    }
+}
+
+async fn send(request: RequestBuilder) -> Result<Response, MicrosoftAuthError> {
+    Ok(request.header("Accept", "application/json").send().await?)
+}
+
+async fn parse_response<T: DeserializeOwned>(response: Response) -> Result<T, MicrosoftAuthError> {
+    if !response.status().is_success() {
+        return Err(MicrosoftAuthError::Status {
+            url: response.url().to_string(),
+            status: response.status().as_u16(),
+        });
+    }
+    Ok(response.json().await?)
+}
+
+async fn json_response<T: DeserializeOwned>(
+    request: RequestBuilder,
+) -> Result<T, MicrosoftAuthError> {
+    parse_response(send(request).await?).await
+}
+
+async fn oauth_error(response: Response) -> MicrosoftAuthError {
+    let url = response.url().to_string();
+    let status = response.status().as_u16();
+    match response.json::<OAuthError>().await {
+        Ok(error) => match error.error.as_str() {
+            "authorization_declined" | "access_denied" => MicrosoftAuthError::AuthorizationDeclined,
+            "expired_token" => MicrosoftAuthError::DeviceCodeExpired,
+            _ => MicrosoftAuthError::OAuth {
+                error: error.error,
+                description: error.error_description,
+            },
+        },
+        Err(_) => MicrosoftAuthError::Status { url, status },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::utils::test_server::{self, Response};
+
+    type Handler = Arc<dyn Fn(&str, &str) -> (u16, serde_json::Value) + Send + Sync>;
+
+    /// Answer every request with `handler(path, body)`.
+    async fn serve(handler: Handler) -> String {
+        test_server::serve(move |request| {
+            let (status, body) = handler(&request.path, &String::from_utf8_lossy(&request.body));
+            Response::new(status, body.to_string()).header("Content-Type", "application/json")
+        })
+        .await
+    }
+
+    fn endpoints(base: &str) -> MicrosoftEndpoints {
+        MicrosoftEndpoints {
+            device_code: format!("{base}/devicecode"),
+            token: format!("{base}/token"),
+            xbox_user: format!("{base}/user/authenticate"),
+            xsts: format!("{base}/xsts/authorize"),
+            minecraft_login: format!("{base}/login_with_xbox"),
+            entitlements: format!("{base}/entitlements"),
+            profile: format!("{base}/profile"),
+        }
+    }
+
+    /// The happy path of every service, `xsts` and `profile` answer the last two steps.
+    fn services(
+        polls: Arc<AtomicUsize>,
+        xsts: (u16, serde_json::Value),
+        profile: (u16, serde_json::Value),
+    ) -> Handler {
+        Arc::new(move |path, body| match path {
+            "/devicecode" => (
+                200,
+                json!({
+                    "device_code": "device",
+                    "user_code": "ABCD-EFGH",
+                    "verification_uri": "https://www.microsoft.com/link",
+                    "expires_in": 900,
+                    "interval": 0,
+                    "message": "Enter ABCD-EFGH"
+                }),
+            ),
+            "/token" if body.contains("grant_type=refresh_token") => {
+                assert!(body.contains("refresh_token=refresh-1"));
+                (
+                    200,
+                    json!({"access_token": "ms-2", "refresh_token": "refresh-2", "expires_in": 3600}),
+                )
+            }
+            "/token" => {
+                assert!(body.contains("device_code=device"));
+                if polls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    (400, json!({"error": "authorization_pending"}))
+                } else {
+                    (
+                        200,
+                        json!({"access_token": "ms-1", "refresh_token": "refresh-1", "expires_in": 3600}),
+                    )
+                }
+            }
+            "/user/authenticate" => {
+                assert!(body.contains("d=ms-"));
+                (
+                    200,
+                    json!({"Token": "xbl", "DisplayClaims": {"xui": [{"uhs": "hash"}]}}),
+                )
+            }
+            "/xsts/authorize" => {
+                assert!(body.contains("\"xbl\""));
+                xsts.clone()
+            }
+            "/login_with_xbox" => {
+                assert!(body.contains("XBL3.0 x=hash;xsts"));
+                (
+                    200,
+                    json!({"access_token": "minecraft", "expires_in": 86400, "username": "x"}),
+                )
+            }
+            "/entitlements" => (200, json!({"items": [{"name": "game_minecraft"}]})),
+            "/profile" => profile.clone(),
+            _ => (404, json!({})),
+        })
+    }
+
+    fn xsts_ok() -> (u16, serde_json::Value) {
+        (
+            200,
+            json!({"Token": "xsts", "DisplayClaims": {"xui": [{"uhs": "hash"}]}}),
+        )
+    }
+
+    #[tokio::test]
+    async fn microsoft_device_code_flow() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let profile = (
+            200,
+            json!({"id": "069a79f444e94726a5befca90e38aaf5", "name": "Notch", "skins": [], "capes": []}),
+        );
+        let base = serve(services(polls.clone(), xsts_ok(), profile)).await;
+        let auth = MicrosoftAuth::new("client").endpoints(endpoints(&base));
+
+        let device_code = auth.request_device_code().await.unwrap();
+        assert_eq!(device_code.user_code, "ABCD-EFGH");
+        let account = auth.login(&device_code).await.unwrap();
+        assert_eq!(polls.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            account.profile,
+            GameProfile {
+                name: "Notch".to_string(),
+                uuid: "069a79f444e94726a5befca90e38aaf5".to_string(),
+            }
+        );
+        assert_eq!(account.access_token, "minecraft");
+        assert_eq!(account.refresh_token, "refresh-1");
+        assert!(!account.is_expired());
+
+        let refreshed = auth.refresh(&account).await.unwrap();
+        assert_eq!(refreshed.refresh_token, "refresh-2");
+        let account: Account = refreshed.into();
+        assert_eq!(account.user_type, UserType::Msa);
+    }
+
+    #[tokio::test]
+    async fn microsoft_account_errors() {
+        let token = MicrosoftToken {
+            access_token: "ms-1".to_string(),
+            refresh_token: "refresh-1".to_string(),
+            expires_at: 0,
+        };
+        let profile = (200, json!({"id": "id", "name": "name"}));
+
+        let child = (
+            401,
+            json!({"Identity": "0", "XErr": 2148916238u64, "Message": ""}),
+        );
+        let base = serve(services(Default::default(), child, profile.clone())).await;
+        let auth = MicrosoftAuth::new("client").endpoints(endpoints(&base));
+        assert_eq!(
+            auth.login_minecraft(&token).await,
+            Err(MicrosoftAuthError::ChildAccount)
+        );
+
+        let no_xbox = (401, json!({"Identity": "0", "XErr": 2148916233u64}));
+        let base = serve(services(Default::default(), no_xbox, profile)).await;
+        let auth = MicrosoftAuth::new("client").endpoints(endpoints(&base));
+        assert_eq!(
+            auth.login_minecraft(&token).await,
+            Err(MicrosoftAuthError::NoXboxAccount)
+        );
+
+        let no_profile = (404, json!({"error": "NOT_FOUND"}));
+        let base = serve(services(Default::default(), xsts_ok(), no_profile)).await;
+        let auth = MicrosoftAuth::new("client").endpoints(endpoints(&base));
+        assert_eq!(
+            auth.login_minecraft(&token).await,
+            Err(MicrosoftAuthError::NoProfile)
+        );
+    }
+}
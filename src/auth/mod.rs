@@ -0,0 +1,57 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Accounts to launch the game with
+//!
+//! An [`Account`] is what the game needs to know about the player: the profile, the access token
+//! and the user type. Set it with [`LaunchOptions::set_account`]. [`OfflineAccount`] needs no
+//! login, a [`microsoft::MicrosoftAccount`] is logged in with [`microsoft::MicrosoftAuth`], and a
+//! [`yggdrasil::YggdrasilAccount`] of an authlib-injector server with
+//! [`yggdrasil::YggdrasilClient`].
+//!
+//! # Example
+//!
+//! ```
+//! use aml_core::auth::OfflineAccount;
+//! use aml_core::core::folder::MinecraftLocation;
+//! use aml_core::launch::options::LaunchOptions;
+//!
+//! async fn fn_name() {
+//!     let minecraft = MinecraftLocation::new(".minecraft");
+//!     let mut options = LaunchOptions::new("1.20.1", &minecraft).await.unwrap();
+//!     options.set_account(OfflineAccount::new("Steve").unwrap());
+//! }
+//! ```
+//!
+//! [`LaunchOptions::set_account`]: crate::launch::options::LaunchOptions::set_account
+
+use crate::launch::options::{GameProfile, UserType};
+
+pub mod microsoft;
+pub mod offline;
+pub mod yggdrasil;
+
+pub use offline::{offline_uuid, validate_username, OfflineAccount, UsernameError};
+
+/// The player of the game
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    pub profile: GameProfile,
+    pub access_token: String,
+    pub user_type: UserType,
+}
@@ -0,0 +1,150 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Offline accounts
+//!
+//! Offline-mode servers and single-player worlds store the player data by the UUID the server
+//! derives from the name, `UUID.nameUUIDFromBytes("OfflinePlayer:" + name)`. An offline account
+//! uses the same UUID, so the inventory is kept between launches.
+
+use md5::{Digest, Md5};
+use uuid::Uuid;
+
+use crate::launch::options::{GameProfile, UserType};
+
+use super::Account;
+
+/// The length limits of a name, as checked by the official launcher.
+const MIN_NAME_LENGTH: usize = 3;
+const MAX_NAME_LENGTH: usize = 16;
+
+/// Why a name can't be used for an offline account
+#[derive(Debug, Clone, PartialEq)]
+pub enum UsernameError {
+    /// Shorter than 3 characters.
+    TooShort,
+
+    /// Longer than 16 characters.
+    TooLong,
+
+    /// Only ASCII letters, digits and `_` are allowed.
+    InvalidCharacter(char),
+}
+
+impl std::fmt::Display for UsernameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UsernameError::TooShort => {
+                write!(f, "username must be at least {MIN_NAME_LENGTH} characters")
+            }
+            UsernameError::TooLong => {
+                write!(f, "username must be at most {MAX_NAME_LENGTH} characters")
+            }
+            UsernameError::InvalidCharacter(c) => write!(f, "invalid character {c:?} in username"),
+        }
+    }
+}
+
+impl std::error::Error for UsernameError {}
+
+/// Check the name like Minecraft does: 3 to 16 ASCII letters, digits or `_`.
+pub fn validate_username(name: &str) -> Result<(), UsernameError> {
+    if let Some(c) = name
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '_')
+    {
+        return Err(UsernameError::InvalidCharacter(c));
+    }
+    match name.len() {
+        len if len < MIN_NAME_LENGTH => Err(UsernameError::TooShort),
+        len if len > MAX_NAME_LENGTH => Err(UsernameError::TooLong),
+        _ => Ok(()),
+    }
+}
+
+/// The UUID an offline-mode server gives the player, a version 3 UUID of `OfflinePlayer:<name>`.
+pub fn offline_uuid(name: &str) -> Uuid {
+    let digest = Md5::digest(format!("OfflinePlayer:{name}").as_bytes());
+    uuid::Builder::from_md5_bytes(digest.into()).into_uuid()
+}
+
+/// An account without authentication
+#[derive(Debug, Clone, PartialEq)]
+pub struct OfflineAccount {
+    pub name: String,
+    pub uuid: Uuid,
+}
+
+impl OfflineAccount {
+    /// An account for `name`, which must be a valid username, see [`validate_username`].
+    pub fn new(name: &str) -> Result<Self, UsernameError> {
+        validate_username(name)?;
+        Ok(Self {
+            name: name.to_string(),
+            uuid: offline_uuid(name),
+        })
+    }
+}
+
+impl From<OfflineAccount> for Account {
+    fn from(account: OfflineAccount) -> Self {
+        let uuid = account.uuid.simple().to_string();
+        Self {
+            profile: GameProfile {
+                name: account.name,
+                uuid: uuid.clone(),
+            },
+            // the game only needs a token which is not empty
+            access_token: uuid,
+            user_type: UserType::Legacy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn offline_account() {
+        assert_eq!(
+            offline_uuid("Steve").to_string(),
+            "5627dd98-e6be-3c21-b8a8-e92344183641"
+        );
+        assert_eq!(offline_uuid("Steve").get_version_num(), 3);
+
+        let account: Account = OfflineAccount::new("Steve").unwrap().into();
+        assert_eq!(account.profile.uuid, "5627dd98e6be3c21b8a8e92344183641");
+        assert_eq!(account.user_type, UserType::Legacy);
+
+        assert!(validate_username("Notch_2009").is_ok());
+        assert_eq!(validate_username("ab"), Err(UsernameError::TooShort));
+        assert_eq!(
+            validate_username("abcdefghijklmnopq"),
+            Err(UsernameError::TooLong)
+        );
+        assert_eq!(
+            validate_username("Steve Jobs"),
+            Err(UsernameError::InvalidCharacter(' '))
+        );
+        assert_eq!(
+            OfflineAccount::new("玩家").unwrap_err().to_string(),
+            "invalid character '玩' in username"
+        );
+    }
+}
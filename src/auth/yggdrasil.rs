@@ -0,0 +1,554 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Yggdrasil accounts of authlib-injector servers
+//!
+//! The game is started with authlib-injector as Java agent, which redirects the authentication of
+//! the game to the server. See
+//! <https://github.com/yushijinhun/authlib-injector/wiki/Yggdrasil-%E6%9C%8D%E5%8A%A1%E7%AB%AF%E6%8A%80%E6%9C%AF%E8%A7%84%E8%8C%83>
+//! for the API.
+//!
+//! # Example
+//!
+//! ```
+//! use aml_core::auth::yggdrasil::YggdrasilClient;
+//! use aml_core::launch::options::LaunchOptions;
+//!
+//! async fn fn_name(mut options: LaunchOptions) {
+//!     let client = YggdrasilClient::discover("littleskin.cn").await.unwrap();
+//!     let authentication = client.authenticate("user@example.com", "password").await.unwrap();
+//!     let profile = authentication.available_profiles[0].clone();
+//!     let account = client.select_profile(&authentication, &profile).await.unwrap();
+//!     options.set_account(account);
+//!     options.set_yggdrasil_agent(Some(client.agent("authlib-injector.jar").await.unwrap()));
+//! }
+//! ```
+
+use std::{fmt::Display, path::Path};
+
+use base64::{engine::general_purpose, Engine};
+use reqwest::{RequestBuilder, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    core::HTTP_CLIENT,
+    launch::options::{GameProfile, UserType, YggdrasilAgent},
+};
+
+use super::Account;
+
+/// The header which points from a website to its Yggdrasil API.
+const API_LOCATION_HEADER: &str = "X-Authlib-Injector-API-Location";
+
+/// Why a request to the Yggdrasil server failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum YggdrasilError {
+    /// The error the server answered with, like `ForbiddenOperationException` for a wrong
+    /// password.
+    Server { error: String, message: String },
+
+    /// The server answered with an unexpected status code.
+    Status { url: String, status: u16 },
+
+    /// The URL of the server is invalid.
+    InvalidUrl(String),
+
+    /// The request failed or the response couldn't be read.
+    Network(String),
+}
+
+impl Display for YggdrasilError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            YggdrasilError::Server { error, message } => write!(f, "{error}: {message}"),
+            YggdrasilError::Status { url, status } => {
+                write!(f, "{url} responded with status {status}")
+            }
+            YggdrasilError::InvalidUrl(url) => write!(f, "invalid url {url}"),
+            YggdrasilError::Network(error) => write!(f, "network error: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for YggdrasilError {}
+
+impl From<reqwest::Error> for YggdrasilError {
+    fn from(value: reqwest::Error) -> Self {
+        YggdrasilError::Network(value.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorResponse {
+    error: String,
+    #[serde(default)]
+    error_message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthResponse {
+    access_token: String,
+    client_token: String,
+    #[serde(default)]
+    available_profiles: Vec<Profile>,
+    selected_profile: Option<Profile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Profile {
+    id: String,
+    name: String,
+}
+
+impl From<Profile> for GameProfile {
+    fn from(profile: Profile) -> Self {
+        Self {
+            name: profile.name,
+            uuid: profile.id,
+        }
+    }
+}
+
+impl From<&GameProfile> for Profile {
+    fn from(profile: &GameProfile) -> Self {
+        Self {
+            id: profile.uuid.clone(),
+            name: profile.name.clone(),
+        }
+    }
+}
+
+/// The result of [`YggdrasilClient::authenticate`]
+///
+/// A user can have several profiles, the server selects one only if there is a single one. Pass
+/// the chosen profile to [`YggdrasilClient::select_profile`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct YggdrasilAuthentication {
+    pub access_token: String,
+    pub client_token: String,
+    pub available_profiles: Vec<GameProfile>,
+    pub selected_profile: Option<GameProfile>,
+}
+
+/// A logged in Yggdrasil account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct YggdrasilAccount {
+    /// The API root of the server.
+    pub server: String,
+    pub access_token: String,
+    pub client_token: String,
+    pub profile: GameProfile,
+}
+
+impl From<YggdrasilAccount> for Account {
+    fn from(account: YggdrasilAccount) -> Self {
+        Self {
+            profile: account.profile,
+            access_token: account.access_token,
+            user_type: UserType::Mojang,
+        }
+    }
+}
+
+/// A client of the Yggdrasil API of an authlib-injector server
+#[derive(Debug, Clone)]
+pub struct YggdrasilClient {
+    api_root: String,
+    client_token: Option<String>,
+}
+
+impl YggdrasilClient {
+    /// A client of the API at `api_root`, like `https://littleskin.cn/api/yggdrasil`.
+    pub fn new(api_root: &str) -> Self {
+        Self {
+            api_root: api_root.trim_end_matches('/').to_string(),
+            client_token: None,
+        }
+    }
+
+    /// Find the API from the address the user entered, which may be the website of the server.
+    ///
+    /// `https://` is added if the address has no scheme, and the `X-Authlib-Injector-API-Location`
+    /// header of the page is followed if present.
+    pub async fn discover(address: &str) -> Result<Self, YggdrasilError> {
+        let address = if address.contains("://") {
+            address.to_string()
+        } else {
+            format!("https://{address}")
+        };
+        let url =
+            Url::parse(&address).map_err(|_| YggdrasilError::InvalidUrl(address.to_string()))?;
+        let response = HTTP_CLIENT.get(url).send().await?;
+        let api_root = match response.headers().get(API_LOCATION_HEADER) {
+            Some(location) => {
+                let location = location
+                    .to_str()
+                    .map_err(|_| YggdrasilError::InvalidUrl(format!("{location:?}")))?;
+                response
+                    .url()
+                    .join(location)
+                    .map_err(|_| YggdrasilError::InvalidUrl(location.to_string()))?
+            }
+            None => response.url().clone(),
+        };
+        Ok(Self::new(api_root.as_str()))
+    }
+
+    /// Use the same client token as an earlier session, one is generated otherwise.
+    pub fn client_token(self, client_token: &str) -> Self {
+        Self {
+            client_token: Some(client_token.to_string()),
+            ..self
+        }
+    }
+
+    pub fn api_root(&self) -> &str {
+        &self.api_root
+    }
+
+    /// The metadata of the server, base64 encoded for
+    /// `-Dauthlibinjector.yggdrasil.prefetched`.
+    pub async fn prefetch(&self) -> Result<String, YggdrasilError> {
+        let response = check_status(HTTP_CLIENT.get(&self.api_root).send().await?).await?;
+        Ok(general_purpose::STANDARD.encode(response.bytes().await?))
+    }
+
+    /// The Java agent to launch the game with, with the prefetched metadata.
+    pub async fn agent<P: AsRef<Path>>(&self, jar: P) -> Result<YggdrasilAgent, YggdrasilError> {
+        Ok(YggdrasilAgent {
+            jar: jar.as_ref().to_path_buf(),
+            server: self.api_root.clone(),
+            prefetched: Some(self.prefetch().await?),
+        })
+    }
+
+    /// Log in with the user name (usually the email) and the password.
+    pub async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<YggdrasilAuthentication, YggdrasilError> {
+        let client_token = self
+            .client_token
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+        let response: AuthResponse = self
+            .post(
+                "authserver/authenticate",
+                json!({
+                    "agent": {"name": "Minecraft", "version": 1},
+                    "username": username,
+                    "password": password,
+                    "clientToken": client_token,
+                    "requestUser": false
+                }),
+            )
+            .await?
+            .json()
+            .await?;
+        Ok(YggdrasilAuthentication {
+            access_token: response.access_token,
+            client_token: response.client_token,
+            available_profiles: response
+                .available_profiles
+                .into_iter()
+                .map(GameProfile::from)
+                .collect(),
+            selected_profile: response.selected_profile.map(GameProfile::from),
+        })
+    }
+
+    /// Bind the session to `profile`, one of [`YggdrasilAuthentication::available_profiles`].
+    pub async fn select_profile(
+        &self,
+        authentication: &YggdrasilAuthentication,
+        profile: &GameProfile,
+    ) -> Result<YggdrasilAccount, YggdrasilError> {
+        if authentication.selected_profile.as_ref() == Some(profile) {
+            return Ok(YggdrasilAccount {
+                server: self.api_root.clone(),
+                access_token: authentication.access_token.clone(),
+                client_token: authentication.client_token.clone(),
+                profile: profile.clone(),
+            });
+        }
+        self.refresh_token(
+            &authentication.access_token,
+            &authentication.client_token,
+            Some(profile),
+        )
+        .await
+    }
+
+    /// Get a new access token, the old one is invalid afterwards.
+    pub async fn refresh(
+        &self,
+        account: &YggdrasilAccount,
+    ) -> Result<YggdrasilAccount, YggdrasilError> {
+        self.refresh_token(&account.access_token, &account.client_token, None)
+            .await
+    }
+
+    /// Whether the access token can still be used to join servers.
+    pub async fn validate(&self, account: &YggdrasilAccount) -> Result<bool, YggdrasilError> {
+        let result = self
+            .post(
+                "authserver/validate",
+                json!({
+                    "accessToken": account.access_token,
+                    "clientToken": account.client_token
+                }),
+            )
+            .await;
+        match result {
+            Ok(_) => Ok(true),
+            Err(YggdrasilError::Server { .. }) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Log out, the access token is invalid afterwards.
+    pub async fn invalidate(&self, account: &YggdrasilAccount) -> Result<(), YggdrasilError> {
+        self.post(
+            "authserver/invalidate",
+            json!({
+                "accessToken": account.access_token,
+                "clientToken": account.client_token
+            }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn refresh_token(
+        &self,
+        access_token: &str,
+        client_token: &str,
+        profile: Option<&GameProfile>,
+    ) -> Result<YggdrasilAccount, YggdrasilError> {
+        let mut body = json!({
+            "accessToken": access_token,
+            "clientToken": client_token,
+            "requestUser": false
+        });
+        if let Some(profile) = profile {
+            body["selectedProfile"] = json!(Profile::from(profile));
+        }
+        let response: AuthResponse = self.post("authserver/refresh", body).await?.json().await?;
+        let profile = response
+            .selected_profile
+            .map(GameProfile::from)
+            .ok_or_else(|| YggdrasilError::Server {
+                error: "IllegalArgumentException".to_string(),
+                message: "no profile is selected".to_string(),
+            })?;
+        Ok(YggdrasilAccount {
+            server: self.api_root.clone(),
+            access_token: response.access_token,
+            client_token: response.client_token,
+            profile,
+        })
+    }
+
+    async fn post(&self, path: &str, body: Value) -> Result<Response, YggdrasilError> {
+        let request: RequestBuilder = HTTP_CLIENT
+            .post(format!("{}/{path}", self.api_root))
+            .json(&body);
+        check_status(request.send().await?).await
+    }
+}
+
+async fn check_status(response: Response) -> Result<Response, YggdrasilError> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let url = response.url().to_string();
+    let status = response.status();
+    match response.json::<ErrorResponse>().await {
+        Ok(error) => Err(YggdrasilError::Server {
+            error: error.error,
+            message: error.error_message,
+        }),
+        Err(_) if status == StatusCode::FORBIDDEN => Err(YggdrasilError::Server {
+            error: "ForbiddenOperationException".to_string(),
+            message: String::new(),
+        }),
+        Err(_) => Err(YggdrasilError::Status {
+            url,
+            status: status.as_u16(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashSet,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::utils::test_server::{self, Response};
+
+    const METADATA: &str =
+        r#"{"meta":{"serverName":"Test"},"skinDomains":[],"signaturePublickey":""}"#;
+
+    /// A Yggdrasil server with one user, who has the profiles `Alex` and `Steve`.
+    async fn serve() -> String {
+        let tokens = Arc::new(Mutex::new(HashSet::new()));
+        test_server::serve(move |request| {
+            let body: Value = serde_json::from_slice(&request.body).unwrap_or_default();
+            // redirect `localhost` to the address of the server
+            let host = request.header("host").unwrap_or_default();
+            let port = host.rsplit(':').next().unwrap_or_default().to_string();
+
+            let profiles = json!([
+                {"id": "a0000000000000000000000000000000", "name": "Alex"},
+                {"id": "50000000000000000000000000000000", "name": "Steve"}
+            ]);
+            let forbidden = (
+                403,
+                json!({
+                    "error": "ForbiddenOperationException",
+                    "errorMessage": "Invalid credentials."
+                })
+                .to_string(),
+            );
+            let (status, body) = {
+                let mut tokens = tokens.lock().unwrap();
+                match request.path.as_str() {
+                    "/" => (200, String::new()),
+                    "/redirect" => (302, String::new()),
+                    "/api/yggdrasil" => (200, METADATA.to_string()),
+                    "/api/yggdrasil/authserver/authenticate" => {
+                        if body["password"] != "password" {
+                            forbidden
+                        } else {
+                            tokens.insert("token-1".to_string());
+                            (
+                                200,
+                                json!({
+                                    "accessToken": "token-1",
+                                    "clientToken": body["clientToken"],
+                                    "availableProfiles": profiles
+                                })
+                                .to_string(),
+                            )
+                        }
+                    }
+                    "/api/yggdrasil/authserver/refresh" => {
+                        let token = body["accessToken"].as_str().unwrap_or_default();
+                        if !tokens.remove(token) {
+                            forbidden
+                        } else {
+                            let new_token = format!("{token}-refreshed");
+                            tokens.insert(new_token.clone());
+                            let selected_profile = if body["selectedProfile"].is_null() {
+                                profiles[1].clone()
+                            } else {
+                                body["selectedProfile"].clone()
+                            };
+                            (
+                                200,
+                                json!({
+                                    "accessToken": new_token,
+                                    "clientToken": body["clientToken"],
+                                    "selectedProfile": selected_profile
+                                })
+                                .to_string(),
+                            )
+                        }
+                    }
+                    "/api/yggdrasil/authserver/validate" => {
+                        if tokens.contains(body["accessToken"].as_str().unwrap_or_default()) {
+                            (204, String::new())
+                        } else {
+                            forbidden
+                        }
+                    }
+                    "/api/yggdrasil/authserver/invalidate" => {
+                        tokens.remove(body["accessToken"].as_str().unwrap_or_default());
+                        (204, String::new())
+                    }
+                    _ => (404, String::new()),
+                }
+            };
+            Response::new(status, body)
+                .header("Location", format!("http://127.0.0.1:{port}/"))
+                .header(API_LOCATION_HEADER, "/api/yggdrasil")
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn yggdrasil_session() {
+        let base = serve().await;
+        let client = YggdrasilClient::discover(&base).await.unwrap();
+        assert_eq!(client.api_root(), format!("{base}/api/yggdrasil"));
+        let redirected = base.replace("127.0.0.1", "localhost");
+        let client = YggdrasilClient::discover(&format!("{redirected}/redirect"))
+            .await
+            .unwrap();
+        assert_eq!(client.api_root(), format!("{base}/api/yggdrasil"));
+
+        let agent = client.agent("authlib-injector.jar").await.unwrap();
+        assert_eq!(
+            general_purpose::STANDARD
+                .decode(agent.prefetched.unwrap())
+                .unwrap(),
+            METADATA.as_bytes()
+        );
+
+        let error = client
+            .authenticate("user@example.com", "wrong")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            YggdrasilError::Server {
+                error: "ForbiddenOperationException".to_string(),
+                message: "Invalid credentials.".to_string(),
+            }
+        );
+
+        let client = client.client_token("client");
+        let authentication = client
+            .authenticate("user@example.com", "password")
+            .await
+            .unwrap();
+        assert_eq!(authentication.client_token, "client");
+        assert_eq!(authentication.selected_profile, None);
+        assert_eq!(authentication.available_profiles.len(), 2);
+        let alex = authentication.available_profiles[0].clone();
+        let account = client.select_profile(&authentication, &alex).await.unwrap();
+        assert_eq!(account.profile, alex);
+        assert_eq!(account.access_token, "token-1-refreshed");
+
+        assert!(client.validate(&account).await.unwrap());
+        let refreshed = client.refresh(&account).await.unwrap();
+        assert!(!client.validate(&account).await.unwrap());
+        client.invalidate(&refreshed).await.unwrap();
+        assert!(!client.validate(&refreshed).await.unwrap());
+
+        let account: Account = refreshed.into();
+        assert_eq!(account.user_type, UserType::Mojang);
+    }
+}
@@ -104,10 +104,49 @@ impl MinecraftLocation {
         }
     }
 
+    /// The cached version manifest.
+    pub fn get_version_manifest(&self) -> PathBuf {
+        self.versions.join("version_manifest_v2.json")
+    }
+
+    /// The `ETag` and `Last-Modified` of the cached version manifest.
+    pub fn get_version_manifest_cache(&self) -> PathBuf {
+        self.versions.join("version_manifest_v2.cache.json")
+    }
+
+    /// The ids of the installed versions, i.e. the folders in `versions` with a `<id>.json`.
+    pub fn list_versions(&self) -> std::io::Result<Vec<String>> {
+        let entries = match std::fs::read_dir(&self.versions) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+        let mut versions = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let id = entry.file_name().to_string_lossy().to_string();
+            if entry.path().join(format!("{id}.json")).is_file() {
+                versions.push(id);
+            }
+        }
+        versions.sort();
+        Ok(versions)
+    }
+
+    /// The Java runtime of the official launcher, like `runtime/java-runtime-gamma`.
+    pub fn get_runtime<P: AsRef<Path>>(&self, component: P) -> PathBuf {
+        self.root.join("runtime").join(component)
+    }
+
     pub fn get_natives_root<P: AsRef<Path>>(&self, version: P) -> PathBuf {
         self.get_version_root(version).join("cvl-natives")
     }
 
+    /// The log4j2 configuration of `logging.client`, which makes the game print XML log events.
+    pub fn get_log4j2_configuration<P: AsRef<Path>>(&self, version: P) -> PathBuf {
+        self.get_version_root(version).join("log4j2.xml")
+    }
+
     pub fn get_version_root<P: AsRef<Path>>(&self, version: P) -> PathBuf {
         self.versions.join(version)
     }
@@ -122,12 +161,13 @@ impl MinecraftLocation {
         version: P,
         r#type: Option<&str>,
     ) -> PathBuf {
-        if r#type == Some("client") || r#type.is_none() {
-            self.get_version_root(&version)
-                .join(format!("{version}.jar"))
-        } else {
-            self.get_version_root(&version)
-                .join(format!("{version}-{}.jar", r#type.unwrap()))
+        match r#type {
+            None | Some("client") => self
+                .get_version_root(&version)
+                .join(format!("{version}.jar")),
+            Some(r#type) => self
+                .get_version_root(&version)
+                .join(format!("{version}-{type}.jar")),
         }
     }
 
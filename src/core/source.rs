@@ -0,0 +1,283 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Download sources (mirrors)
+//!
+//! Everything in this crate produces the official url of a file, like
+//! `https://libraries.minecraft.net/...` or `https://meta.fabricmc.net/...`. A [`DownloadSource`]
+//! rewrites such an url to the same file on a mirror, and [`NetworkOptions`] holds the sources
+//! that are tried in order until one of them succeeds.
+//!
+//! # Example
+//!
+//! Prefer BMCLAPI, and fall back to the official servers:
+//!
+//! ```
+//! use aml_core::core::source::{set_network_options, DownloadSource, NetworkOptions};
+//!
+//! set_network_options(NetworkOptions {
+//!     sources: vec![DownloadSource::bmclapi(), DownloadSource::official()],
+//! });
+//! ```
+//!
+//! Use a self-hosted mirror which only serves libraries:
+//!
+//! ```
+//! use aml_core::core::source::DownloadSource;
+//!
+//! let source = DownloadSource::new("my mirror")
+//!     .replace("https://libraries.minecraft.net", "https://mirror.example.com/libraries");
+//! ```
+
+use std::sync::RwLock;
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use reqwest::{RequestBuilder, Response};
+
+use super::HTTP_CLIENT;
+
+const BMCLAPI: &str = "https://bmclapi2.bangbang93.com";
+
+/// The official hosts and where BMCLAPI compatible mirrors serve them, relative to the mirror root.
+static BMCLAPI_LAYOUT: &[(&str, &str)] = &[
+    ("https://launchermeta.mojang.com", ""),
+    ("https://launcher.mojang.com", ""),
+    ("https://piston-meta.mojang.com", ""),
+    ("https://piston-data.mojang.com", ""),
+    ("https://resources.download.minecraft.net", "/assets"),
+    ("https://libraries.minecraft.net", "/maven"),
+    ("https://maven.minecraftforge.net", "/maven"),
+    ("https://files.minecraftforge.net/maven", "/maven"),
+    ("https://maven.neoforged.net/releases", "/maven"),
+    ("https://maven.fabricmc.net", "/maven"),
+    ("https://meta.fabricmc.net", "/fabric-meta"),
+    ("https://maven.quiltmc.org/repository/release", "/maven"),
+    (BMCLAPI, ""),
+];
+
+/// A place to download files from.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DownloadSource {
+    pub name: String,
+
+    /// Pairs of url prefixes, the official prefix and the prefix on this source.
+    ///
+    /// The first matching pair wins, urls without a matching pair are used as is.
+    pub replacements: Vec<(String, String)>,
+}
+
+impl DownloadSource {
+    /// A source without any replacement, add them with `DownloadSource::replace()`.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            replacements: vec![],
+        }
+    }
+
+    /// Rewrite the urls starting with `official` to start with `mirror` instead.
+    pub fn replace(mut self, official: &str, mirror: &str) -> Self {
+        self.replacements.push((
+            official.to_string(),
+            mirror.trim_end_matches('/').to_string(),
+        ));
+        self
+    }
+
+    /// Mojang, Forge, Fabric and Quilt official servers.
+    pub fn official() -> Self {
+        Self::new("official")
+    }
+
+    /// [BMCLAPI](https://bmclapi2.bangbang93.com)
+    pub fn bmclapi() -> Self {
+        let mut source = Self::custom(BMCLAPI);
+        source.name = "bmclapi".to_string();
+        source
+    }
+
+    /// A mirror that has the same layout as BMCLAPI, hosted at `base_url`.
+    pub fn custom(base_url: &str) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        BMCLAPI_LAYOUT
+            .iter()
+            .fold(Self::new(base_url), |source, (official, path)| {
+                source.replace(official, &format!("{base_url}{path}"))
+            })
+    }
+
+    /// Get the url of the file on this source.
+    pub fn resolve(&self, url: &str) -> String {
+        for (official, mirror) in &self.replacements {
+            if let Some(rest) = url.strip_prefix(official.as_str()) {
+                if rest.is_empty() || rest.starts_with('/') || official.ends_with('/') {
+                    return format!("{mirror}{rest}");
+                }
+            }
+        }
+        url.to_string()
+    }
+}
+
+/// Network settings, used by every function in this crate that accesses the network.
+///
+/// Change it for the whole process with [`set_network_options`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NetworkOptions {
+    /// The sources to try, from the first to the last.
+    pub sources: Vec<DownloadSource>,
+}
+
+impl Default for NetworkOptions {
+    fn default() -> Self {
+        Self {
+            sources: vec![DownloadSource::official()],
+        }
+    }
+}
+
+impl NetworkOptions {
+    /// All the urls of the file, in the order they should be tried.
+    pub fn candidates(&self, url: &str) -> Vec<String> {
+        let mut candidates: Vec<String> = Vec::with_capacity(self.sources.len());
+        for source in &self.sources {
+            let candidate = source.resolve(url);
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+        if candidates.is_empty() {
+            candidates.push(url.to_string());
+        }
+        candidates
+    }
+
+    /// Send a GET request to the first source that responds successfully.
+    pub async fn get(&self, url: &str) -> Result<Response> {
+        self.get_with(url, |request| request).await
+    }
+
+    /// Like [`NetworkOptions::get`], `build` can add headers to the request sent to each source.
+    pub async fn get_with<F>(&self, url: &str, build: F) -> Result<Response>
+    where
+        F: Fn(RequestBuilder) -> RequestBuilder,
+    {
+        let mut last_error = anyhow!("No source available for {url}");
+        for candidate in self.candidates(url) {
+            match build(HTTP_CLIENT.get(&candidate)).send().await {
+                Ok(response) => match response.error_for_status() {
+                    Ok(response) => return Ok(response),
+                    Err(error) => last_error = error.into(),
+                },
+                Err(error) => last_error = error.into(),
+            }
+        }
+        Err(last_error)
+    }
+}
+
+static NETWORK_OPTIONS: Lazy<RwLock<NetworkOptions>> =
+    Lazy::new(|| RwLock::new(NetworkOptions::default()));
+
+/// Replace the network options of the whole process.
+pub fn set_network_options(options: NetworkOptions) {
+    *NETWORK_OPTIONS.write().unwrap() = options;
+}
+
+/// The network options currently in use.
+pub fn network_options() -> NetworkOptions {
+    NETWORK_OPTIONS.read().unwrap().clone()
+}
+
+/// Send a GET request through the current network options.
+pub(crate) async fn get(url: &str) -> Result<Response> {
+    network_options().get(url).await
+}
+
+/// Send a GET request through the current network options, see [`NetworkOptions::get_with`].
+pub(crate) async fn get_with<F>(url: &str, build: F) -> Result<Response>
+where
+    F: Fn(RequestBuilder) -> RequestBuilder,
+{
+    network_options().get_with(url, build).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolve_urls() {
+        let bmclapi = DownloadSource::bmclapi();
+        assert_eq!(
+            bmclapi
+                .resolve("https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar"),
+            "https://bmclapi2.bangbang93.com/maven/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar"
+        );
+        assert_eq!(
+            bmclapi.resolve("https://resources.download.minecraft.net/ab/abcdef"),
+            "https://bmclapi2.bangbang93.com/assets/ab/abcdef"
+        );
+        assert_eq!(
+            bmclapi.resolve("https://piston-meta.mojang.com/mc/game/version_manifest_v2.json"),
+            "https://bmclapi2.bangbang93.com/mc/game/version_manifest_v2.json"
+        );
+        // not a prefix on a path boundary
+        assert_eq!(
+            bmclapi.resolve("https://libraries.minecraft.net.example.com/a.jar"),
+            "https://libraries.minecraft.net.example.com/a.jar"
+        );
+
+        let custom = DownloadSource::custom("https://mirror.example.com/");
+        assert_eq!(
+            custom.resolve("https://bmclapi2.bangbang93.com/forge/minecraft/1.20.1"),
+            "https://mirror.example.com/forge/minecraft/1.20.1"
+        );
+        assert_eq!(
+            custom.resolve("https://maven.fabricmc.net/net/fabricmc/yarn/"),
+            "https://mirror.example.com/maven/net/fabricmc/yarn/"
+        );
+        assert_eq!(
+            DownloadSource::official().resolve("https://meta.fabricmc.net/v2/versions"),
+            "https://meta.fabricmc.net/v2/versions"
+        );
+    }
+
+    #[test]
+    fn candidates_in_order() {
+        let options = NetworkOptions {
+            sources: vec![
+                DownloadSource::bmclapi(),
+                DownloadSource::official(),
+                DownloadSource::new("empty"),
+            ],
+        };
+        assert_eq!(
+            options.candidates("https://meta.fabricmc.net/v2/versions"),
+            vec![
+                "https://bmclapi2.bangbang93.com/fabric-meta/v2/versions",
+                "https://meta.fabricmc.net/v2/versions",
+            ]
+        );
+        assert_eq!(
+            NetworkOptions { sources: vec![] }.candidates("https://a.b/c"),
+            vec!["https://a.b/c"]
+        );
+    }
+}
@@ -25,12 +25,12 @@
 //! ```
 //! use aml_core::core::folder::MinecraftLocation;
 //! use aml_core::core::task::TaskEventListeners;
-//! use aml_core::install::install;
+//! use aml_core::install::install_vanilla;
 //!  async fn fn_name() {
 //!     let listeners = TaskEventListeners::default().on_progress(Box::new(|completed, total, step| {
 //!         println!("progress: {completed}/{total}; step: {step}")
 //!     }));
-//!     install("1.19.4", MinecraftLocation::new(".minecraft"), listeners).await.unwrap();
+//!     install_vanilla("1.19.4", MinecraftLocation::new(".minecraft"), listeners).await.unwrap();
 //! }
 //! ```
 
@@ -58,10 +58,10 @@
 /// ```
 pub struct TaskEventListeners {
     // todo: 改成 Vec<Box<dyn Fn()>>，以允许执行多个异步
-    on_start: Box<dyn Fn()>,
-    on_progress: Box<dyn Fn(usize, usize, usize)>,
-    on_succeed: Box<dyn Fn()>,
-    on_failed: Box<dyn Fn()>,
+    on_start: Box<dyn Fn() + Send + Sync>,
+    on_progress: Box<dyn Fn(usize, usize, usize) + Send + Sync>,
+    on_succeed: Box<dyn Fn() + Send + Sync>,
+    on_failed: Box<dyn Fn() + Send + Sync>,
 }
 
 impl Default for TaskEventListeners {
@@ -79,22 +79,22 @@ impl Default for TaskEventListeners {
 
 impl TaskEventListeners {
     /// Register the start event listener, when the task start, the event will be triggered
-    pub fn on_start(self, on_start: Box<dyn Fn()>) -> Self {
+    pub fn on_start(self, on_start: Box<dyn Fn() + Send + Sync>) -> Self {
         Self { on_start, ..self }
     }
     /// Register the progress event listener, when the task progress, the event will be triggered
-    pub fn on_progress(self, on_progress: Box<dyn Fn(usize, usize, usize)>) -> Self {
+    pub fn on_progress(self, on_progress: Box<dyn Fn(usize, usize, usize) + Send + Sync>) -> Self {
         Self {
             on_progress,
             ..self
         }
     }
     /// Register the succeed event listener, when the task succeed, the event will be triggered
-    pub fn on_succeed(self, on_succeed: Box<dyn Fn()>) -> Self {
+    pub fn on_succeed(self, on_succeed: Box<dyn Fn() + Send + Sync>) -> Self {
         Self { on_succeed, ..self }
     }
     /// Register the failed event listener, when the task failed, the event will be triggered
-    pub fn on_failed(self, on_failed: Box<dyn Fn()>) -> Self {
+    pub fn on_failed(self, on_failed: Box<dyn Fn() + Send + Sync>) -> Self {
         Self { on_failed, ..self }
     }
     pub(crate) fn start(&self) {
@@ -26,7 +26,7 @@ use serde_json::Value;
 
 use crate::core::folder::MinecraftLocation;
 
-use super::PlatformInfo;
+use super::{source, PlatformInfo};
 
 static DEFAULT_GAME_ARGS: Lazy<Vec<String>> = Lazy::new(|| {
     vec![
@@ -61,28 +61,31 @@ static DEFAULT_GAME_ARGS: Lazy<Vec<String>> = Lazy::new(|| {
 
 static DEFAULT_JVM_ARGS: Lazy<Vec<String>> = Lazy::new(|| {
     vec![
-        "\"-Djava.library.path=${natives_directory}\"".to_string(),
-        // "\"-Djna.tmpdir=${natives_directory}\"".to_string(),
-        // "\"-Dorg.lwjgl.system.SharedLibraryExtractPath=${natives_directory}\"".to_string(),
-        // "\"-Dio.netty.native.workdir=${natives_directory}\"".to_string(),
-        "\"-Dminecraft.launcher.brand=${launcher_name}\"".to_string(),
-        "\"-Dminecraft.launcher.version=${launcher_version}\"".to_string(),
-        "\"-Dfile.encoding=UTF-8\"".to_string(),
-        "\"-Dsun.stdout.encoding=UTF-8\"".to_string(),
-        "\"-Dsun.stderr.encoding=UTF-8\"".to_string(),
-        "\"-Djava.rmi.server.useCodebaseOnly=true\"".to_string(),
-        "\"-XX:MaxInlineSize=420\"".to_string(),
-        "\"-XX:-UseAdaptiveSizePolicy\"".to_string(),
-        "\"-XX:-OmitStackTraceInFastThrow\"".to_string(),
-        "\"-XX:-DontCompileHugeMethods\"".to_string(),
-        "\"-Dcom.sun.jndi.rmi.object.trustURLCodebase=false\"".to_string(),
-        "\"-Dcom.sun.jndi.cosnaming.object.trustURLCodebase=false\"".to_string(),
-        "\"-Dlog4j2.formatMsgNoLookups=true\"".to_string(),
+        "-Djava.library.path=${natives_directory}".to_string(),
+        // "-Djna.tmpdir=${natives_directory}".to_string(),
+        // "-Dorg.lwjgl.system.SharedLibraryExtractPath=${natives_directory}".to_string(),
+        // "-Dio.netty.native.workdir=${natives_directory}".to_string(),
+        "-Dminecraft.launcher.brand=${launcher_name}".to_string(),
+        "-Dminecraft.launcher.version=${launcher_version}".to_string(),
+        "-Dfile.encoding=UTF-8".to_string(),
+        "-Dsun.stdout.encoding=UTF-8".to_string(),
+        "-Dsun.stderr.encoding=UTF-8".to_string(),
+        "-Djava.rmi.server.useCodebaseOnly=true".to_string(),
+        "-XX:MaxInlineSize=420".to_string(),
+        "-XX:-UseAdaptiveSizePolicy".to_string(),
+        "-XX:-OmitStackTraceInFastThrow".to_string(),
+        "-XX:-DontCompileHugeMethods".to_string(),
+        "-Dcom.sun.jndi.rmi.object.trustURLCodebase=false".to_string(),
+        "-Dcom.sun.jndi.cosnaming.object.trustURLCodebase=false".to_string(),
+        "-Dlog4j2.formatMsgNoLookups=true".to_string(),
         "-cp".to_string(),
         "${classpath}".to_string(),
     ]
 });
 
+const VERSION_MANIFEST_URL: &str =
+    "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct LatestVersion {
     pub release: String,
@@ -107,12 +110,99 @@ pub struct VersionManifest {
     pub versions: Vec<VersionInfo>,
 }
 
+/// The validators of the cached version manifest, saved next to it.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct VersionManifestCache {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
 impl VersionManifest {
+    /// Fetch the version manifest, always from the network.
     pub async fn new() -> Result<VersionManifest> {
-        let response =
-            reqwest::get("https://piston-meta.mojang.com/mc/game/version_manifest_v2.json").await?;
+        let response = source::get(VERSION_MANIFEST_URL).await?;
         Ok(response.json::<VersionManifest>().await?)
     }
+
+    /// Get the version manifest, with a cache in `versions/version_manifest_v2.json`.
+    ///
+    /// The cached copy is revalidated with `If-None-Match`/`If-Modified-Since`. If the network is
+    /// not available, the cached copy is used as is. Only fails if there is no network and no
+    /// cache.
+    pub async fn cached(minecraft: &MinecraftLocation) -> Result<VersionManifest> {
+        Self::cached_from(VERSION_MANIFEST_URL, minecraft).await
+    }
+
+    pub(crate) async fn cached_from(
+        url: &str,
+        minecraft: &MinecraftLocation,
+    ) -> Result<VersionManifest> {
+        let manifest_path = minecraft.get_version_manifest();
+        let cache_path = minecraft.get_version_manifest_cache();
+        let cached = tokio::fs::read_to_string(&manifest_path)
+            .await
+            .ok()
+            .and_then(|manifest| serde_json::from_str::<VersionManifest>(&manifest).ok());
+        let validators = match cached {
+            Some(_) => tokio::fs::read_to_string(&cache_path)
+                .await
+                .ok()
+                .and_then(|cache| serde_json::from_str::<VersionManifestCache>(&cache).ok())
+                .unwrap_or_default(),
+            None => VersionManifestCache::default(),
+        };
+
+        let response = source::get_with(url, |request| {
+            let request = match &validators.etag {
+                Some(etag) => request.header(reqwest::header::IF_NONE_MATCH, etag),
+                None => request,
+            };
+            match &validators.last_modified {
+                Some(last_modified) => {
+                    request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified)
+                }
+                None => request,
+            }
+        })
+        .await;
+        let response = match (response, cached) {
+            (Ok(response), Some(cached))
+                if response.status() == reqwest::StatusCode::NOT_MODIFIED =>
+            {
+                return Ok(cached)
+            }
+            (Ok(response), _) => response,
+            (Err(_), Some(cached)) => return Ok(cached),
+            (Err(error), None) => return Err(error),
+        };
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let validators = VersionManifestCache {
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+        };
+        let text = response.text().await?;
+        let manifest = serde_json::from_str::<VersionManifest>(&text)?;
+        tokio::fs::create_dir_all(&minecraft.versions).await?;
+        tokio::fs::write(&manifest_path, text).await?;
+        tokio::fs::write(&cache_path, serde_json::to_string(&validators)?).await?;
+        Ok(manifest)
+    }
+
+    /// Find a version by id.
+    pub fn find(&self, id: &str) -> Result<&VersionInfo, VersionError> {
+        self.versions
+            .iter()
+            .find(|version| version.id == id)
+            .ok_or_else(|| VersionError::UnknownVersion(id.to_string()))
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -125,7 +215,7 @@ pub struct Download {
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct AssetIndex {
-    // pub sha1: String,
+    pub sha1: Option<String>,
     pub size: u64,
     pub url: String,
     pub id: String,
@@ -145,7 +235,9 @@ pub type AssetIndexObject = HashMap<String, AssetIndexObjectInfo>;
 pub struct LibraryDownload {
     pub sha1: Option<String>,
     pub size: Option<u64>,
+    #[serde(default)]
     pub url: String,
+    #[serde(default)]
     pub path: String,
 }
 
@@ -156,12 +248,6 @@ pub struct LoggingFile {
     pub id: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
-pub struct NormalLibrary {
-    pub name: String,
-    pub downloads: HashMap<String, LibraryDownload>,
-}
-
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Rule {
     pub action: String,
@@ -169,62 +255,185 @@ pub struct Rule {
     pub features: Option<HashMap<String, bool>>,
 }
 
+impl Rule {
+    /// Whether the rule applies to the platform and the enabled features.
+    ///
+    /// The `os` must match if present, and every feature of the rule must be equal to the
+    /// enabled one, a feature that is not in `features` is disabled.
+    pub fn is_matched(&self, platform: &PlatformInfo, features: &HashMap<String, bool>) -> bool {
+        if let Some(os) = &self.os {
+            if !os.is_matched(platform) {
+                return false;
+            }
+        }
+        if let Some(rule_features) = &self.features {
+            return rule_features
+                .iter()
+                .all(|(name, value)| features.get(name).copied().unwrap_or(false) == *value);
+        }
+        true
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Extract {
+    #[serde(default)]
     pub exclude: Vec<String>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
-pub struct NativeLibrary {
-    pub name: String,
-    pub downloads: HashMap<String, LibraryDownload>,
-    pub classifiers: HashMap<String, LibraryDownload>,
-    pub rules: Vec<Rule>,
-    pub extract: Extract,
-    pub natives: HashMap<String, String>,
-}
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub struct LibraryDownloads {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artifact: Option<LibraryDownload>,
 
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
-pub struct PlatformSpecificLibrary {
-    pub name: String,
-    pub downloads: HashMap<String, LibraryDownload>,
-    pub rules: Vec<Rule>,
+    /// The native libraries before 1.19, the key is the classifier.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub classifiers: Option<HashMap<String, LibraryDownload>>,
 }
 
+/// A library in version.json
+///
+/// Covers the vanilla libraries with `downloads`, the natives with `natives` and `classifiers`,
+/// and the mod loader libraries which only have a `name` and a maven `url`.
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
-pub struct LegacyLibrary {
+pub struct Library {
+    /// The maven name, `group:artifact:version[:classifier][@extension]`
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<LibraryDownloads>,
+
+    /// The maven repository of the library, used when there is no `downloads`.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
-    pub clientreq: Option<bool>,
-    pub serverreq: Option<bool>,
-    pub checksums: Option<Vec<String>>,
-}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Vec<Rule>>,
 
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
-pub enum Library {
-    Normal(NormalLibrary),
-    Native(NativeLibrary),
-    PlatformSpecific(PlatformSpecificLibrary),
-    Legacy(LegacyLibrary),
+    /// The classifier of the native library for each os, may contain `${arch}`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub natives: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extract: Option<Extract>,
+
+    /// Other fields like `clientreq` and `checksums` of the legacy forge.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
-pub enum LaunchArgument {
-    String(String),
-    Object(serde_json::map::Map<String, Value>),
+impl Library {
+    /// The key to tell if two libraries are the same one, `group:artifact[:classifier]`.
+    ///
+    /// Natives of the same artifact have different classifiers, so they are not the same one.
+    pub fn key(&self) -> String {
+        let name = self.name.split('@').next().unwrap_or_default();
+        let mut parts = name.split(':');
+        let group = parts.next().unwrap_or_default();
+        let artifact = parts.next().unwrap_or_default();
+        match parts.nth(1) {
+            Some(classifier) => format!("{group}:{artifact}:{classifier}"),
+            None => format!("{group}:{artifact}"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Platform {
-    pub name: String,
+    pub name: Option<String>,
+
+    /// A regex matched against the os version, like `^10\.` for Windows 10
     pub version: Option<String>,
-    // Add other platform properties if needed
+    pub arch: Option<String>,
+}
+
+impl Platform {
+    pub fn is_matched(&self, platform: &PlatformInfo) -> bool {
+        if let Some(name) = &self.name {
+            if &platform.name != name {
+                return false;
+            }
+        }
+        if let Some(arch) = &self.arch {
+            if normalize_arch(&platform.arch) != normalize_arch(arch) {
+                return false;
+            }
+        }
+        if let Some(version) = &self.version {
+            return Regex::new(version)
+                .map(|regex| regex.is_match(platform.version.trim()))
+                .unwrap_or(false);
+        }
+        true
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Arguments {
-    pub game: Option<Vec<Value>>,
-    pub jvm: Option<Vec<Value>>,
+    pub game: Option<Vec<Argument>>,
+    pub jvm: Option<Vec<Argument>>,
+}
+
+/// A launch argument, the `value` is only used when the `rules` allow it.
+///
+/// In version.json, it's either a string, or an object with `rules` and a `value` which can be
+/// a string or an array of strings.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(from = "RawArgument", into = "RawArgument")]
+pub struct Argument {
+    pub rules: Vec<Rule>,
+    pub value: Vec<String>,
+}
+
+impl<S: AsRef<str>> From<S> for Argument {
+    fn from(value: S) -> Self {
+        Self {
+            rules: vec![],
+            value: vec![value.as_ref().to_string()],
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum RawArgument {
+    String(String),
+    Object {
+        #[serde(default)]
+        rules: Vec<Rule>,
+        value: RawArgumentValue,
+    },
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum RawArgumentValue {
+    String(String),
+    Array(Vec<String>),
+}
+
+impl From<RawArgument> for Argument {
+    fn from(raw: RawArgument) -> Self {
+        match raw {
+            RawArgument::String(value) => Argument::from(value),
+            RawArgument::Object { rules, value } => Argument {
+                rules,
+                value: match value {
+                    RawArgumentValue::String(value) => vec![value],
+                    RawArgumentValue::Array(values) => values,
+                },
+            },
+        }
+    }
+}
+
+impl From<Argument> for RawArgument {
+    fn from(argument: Argument) -> Self {
+        match (argument.rules.is_empty(), argument.value.len()) {
+            (true, 1) => RawArgument::String(argument.value.into_iter().next().unwrap()),
+            _ => RawArgument::Object {
+                rules: argument.rules,
+                value: RawArgumentValue::Array(argument.value),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -251,14 +460,113 @@ pub struct JavaVersion {
 
 /// Minecraft Version
 ///
-/// It used to compare the version of the game
-#[derive(Debug, Clone, Serialize)]
+/// It used to compare the version of the game, for example:
+///
+/// ```
+/// use aml_core::core::version::MinecraftVersion;
+///
+/// let version: MinecraftVersion = "1.20.1-rc1".parse().unwrap();
+/// assert!(version >= "1.20".parse().unwrap());
+/// assert!(version < "1.20.1".parse().unwrap());
+/// ```
+///
+/// Snapshots are ordered before the release they lead to, old alpha, beta and classic versions
+/// are ordered before all the releases.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum MinecraftVersion {
+    /// `1.20.1`, `1.8`
     Release(u8, u8, Option<u8>),
+
+    /// `1.20-pre1`, `1.14 Pre-Release 2`
+    PreRelease(u8, u8, Option<u8>, u8),
+
+    /// `1.20.1-rc1`
+    ReleaseCandidate(u8, u8, Option<u8>, u8),
+
+    /// `23w13a`, the year, the week and the rest
     Snapshot(u8, u8, String),
+
+    /// `b1.7.3`
+    Beta(String),
+
+    /// `a1.0.4`
+    Alpha(String),
+
+    /// `inf-20100618`
+    Infdev(String),
+
+    /// `c0.30_01c`, and the pre-classic `rd-132211`
+    Classic(String),
+
+    /// April Fools versions not in the snapshot format, like `1.RV-Pre1` or `3D Shareware v1.34`.
+    AprilFools(String),
     Unknown(String),
 }
 
+/// April Fools versions which are not snapshots, and the week they were released.
+static APRIL_FOOLS: &[(&str, u8, u8)] = &[
+    ("2.0", 13, 14),
+    ("1.RV-Pre1", 16, 14),
+    ("3D Shareware v1.34", 19, 14),
+];
+
+/// The major, minor and patch number of a release.
+type ReleaseNumber = (u8, u8, Option<u8>);
+
+/// Releases that have snapshots, and the week they were released.
+///
+/// A snapshot leads to the first release after it.
+static SNAPSHOT_TARGETS: &[(ReleaseNumber, u8, u8)] = &[
+    ((1, 1, None), 12, 2),
+    ((1, 2, Some(1)), 12, 9),
+    ((1, 3, Some(1)), 12, 31),
+    ((1, 4, Some(2)), 12, 43),
+    ((1, 4, Some(6)), 12, 51),
+    ((1, 5, None), 13, 11),
+    ((1, 5, Some(1)), 13, 12),
+    ((1, 6, Some(1)), 13, 27),
+    ((1, 7, Some(2)), 13, 43),
+    ((1, 7, Some(4)), 13, 50),
+    ((1, 8, None), 14, 36),
+    ((1, 9, None), 16, 9),
+    ((1, 9, Some(3)), 16, 19),
+    ((1, 10, None), 16, 23),
+    ((1, 11, None), 16, 46),
+    ((1, 11, Some(1)), 16, 51),
+    ((1, 12, None), 17, 23),
+    ((1, 12, Some(1)), 17, 31),
+    ((1, 13, None), 18, 29),
+    ((1, 13, Some(1)), 18, 34),
+    ((1, 14, None), 19, 17),
+    ((1, 15, None), 19, 50),
+    ((1, 16, None), 20, 26),
+    ((1, 16, Some(2)), 20, 33),
+    ((1, 17, None), 21, 23),
+    ((1, 18, None), 21, 48),
+    ((1, 19, None), 22, 23),
+    ((1, 19, Some(1)), 22, 30),
+    ((1, 19, Some(3)), 22, 49),
+    ((1, 19, Some(4)), 23, 11),
+    ((1, 20, None), 23, 23),
+    ((1, 20, Some(2)), 23, 38),
+    ((1, 20, Some(3)), 23, 49),
+    ((1, 20, Some(5)), 24, 17),
+    ((1, 21, None), 24, 24),
+    ((1, 21, Some(2)), 24, 43),
+    ((1, 21, Some(4)), 24, 49),
+    ((1, 21, Some(5)), 25, 13),
+    ((1, 21, Some(6)), 25, 25),
+    ((1, 21, Some(9)), 25, 40),
+];
+
+static RELEASE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\d+)\.(\d+)(?:\.(\d+))?$").unwrap());
+static PRE_RELEASE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\d+)\.(\d+)(?:\.(\d+))?(?:-pre| Pre-Release )(\d+)$").unwrap());
+static RELEASE_CANDIDATE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\d+)\.(\d+)(?:\.(\d+))?-rc(\d+)$").unwrap());
+static SNAPSHOT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d{2})w(\d{2})(.+)$").unwrap());
+
 impl FromStr for MinecraftVersion {
     type Err = anyhow::Error;
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
@@ -267,26 +575,164 @@ impl FromStr for MinecraftVersion {
 }
 
 fn parse_version(s: &str) -> Result<MinecraftVersion> {
-    if s.contains(".") {
-        let split = s.split(".").collect::<Vec<&str>>();
-        Ok(MinecraftVersion::Release(
-            split.get(0).ok_or(anyhow::anyhow!(""))?.parse()?,
-            split.get(1).ok_or(anyhow::anyhow!(""))?.parse()?,
-            match split.get(2) {
-                Some(x) => Some(x.parse()?),
-                None => None,
-            },
-        ))
-    } else if s.contains("w") {
-        let split = s.split("w").collect::<Vec<&str>>();
-        let minor_version = split.get(1).ok_or(anyhow::anyhow!(""))?;
-        Ok(MinecraftVersion::Snapshot(
-            split.get(0).ok_or(anyhow::anyhow!(""))?.parse()?,
-            (&minor_version[..2]).parse()?,
-            (&minor_version[2..]).to_string(),
+    fn number<T: FromStr>(captures: &regex::Captures, i: usize) -> Result<Option<T>> {
+        match captures.get(i) {
+            Some(x) => Ok(Some(x.as_str().parse().map_err(|_| {
+                anyhow::anyhow!("Version number out of range: {}", x.as_str())
+            })?)),
+            None => Ok(None),
+        }
+    }
+    let release = |captures: &regex::Captures| -> Result<ReleaseNumber> {
+        Ok((
+            number(captures, 1)?.unwrap(),
+            number(captures, 2)?.unwrap(),
+            number(captures, 3)?,
         ))
+    };
+
+    if APRIL_FOOLS.iter().any(|(id, _, _)| *id == s) {
+        return Ok(MinecraftVersion::AprilFools(s.to_string()));
+    }
+    if let Some(captures) = RELEASE_REGEX.captures(s) {
+        let (major, minor, patch) = release(&captures)?;
+        return Ok(MinecraftVersion::Release(major, minor, patch));
+    }
+    if let Some(captures) = PRE_RELEASE_REGEX.captures(s) {
+        let (major, minor, patch) = release(&captures)?;
+        let pre = number(&captures, 4)?.unwrap();
+        return Ok(MinecraftVersion::PreRelease(major, minor, patch, pre));
+    }
+    if let Some(captures) = RELEASE_CANDIDATE_REGEX.captures(s) {
+        let (major, minor, patch) = release(&captures)?;
+        let rc = number(&captures, 4)?.unwrap();
+        return Ok(MinecraftVersion::ReleaseCandidate(major, minor, patch, rc));
+    }
+    if let Some(captures) = SNAPSHOT_REGEX.captures(s) {
+        return Ok(MinecraftVersion::Snapshot(
+            number(&captures, 1)?.unwrap(),
+            number(&captures, 2)?.unwrap(),
+            captures[3].to_string(),
+        ));
+    }
+    let starts_with_digit = |prefix: &str| matches!(s.strip_prefix(prefix), Some(x) if x.starts_with(|c: char| c.is_ascii_digit()));
+    Ok(if starts_with_digit("b") {
+        MinecraftVersion::Beta(s.to_string())
+    } else if starts_with_digit("a") {
+        MinecraftVersion::Alpha(s.to_string())
+    } else if starts_with_digit("inf-") {
+        MinecraftVersion::Infdev(s.to_string())
+    } else if starts_with_digit("c") || starts_with_digit("rd-") {
+        MinecraftVersion::Classic(s.to_string())
     } else {
-        Ok(MinecraftVersion::Unknown(s.to_string()))
+        MinecraftVersion::Unknown(s.to_string())
+    })
+}
+
+/// A part of a version string, digits are compared as numbers.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum VersionPart<'a> {
+    Number(u64),
+    Text(&'a str),
+}
+
+fn version_parts(s: &str) -> Vec<VersionPart<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        let is_digit = c.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != is_digit)
+            .unwrap_or(rest.len());
+        let (part, tail) = rest.split_at(end);
+        parts.push(match part.parse() {
+            Ok(number) if is_digit => VersionPart::Number(number),
+            _ => VersionPart::Text(part),
+        });
+        rest = tail;
+    }
+    parts
+}
+
+/// The key used to order the versions, the variants are in chronological order.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum VersionOrder<'a> {
+    PreClassic(Vec<VersionPart<'a>>),
+    Classic(Vec<VersionPart<'a>>),
+    Infdev(Vec<VersionPart<'a>>),
+    Alpha(Vec<VersionPart<'a>>),
+    Beta(Vec<VersionPart<'a>>),
+
+    /// The release, then snapshots < pre-releases < release candidates < the release itself.
+    Modern {
+        release: ReleaseNumber,
+        stage: u8,
+        number: (u8, u8, u8),
+        name: &'a str,
+    },
+    Unknown(&'a str),
+}
+
+impl MinecraftVersion {
+    fn order(&self) -> VersionOrder<'_> {
+        // snapshots newer than all the known releases lead to an unknown release after them
+        let snapshot = |year: u8, week: u8, name| VersionOrder::Modern {
+            release: SNAPSHOT_TARGETS
+                .iter()
+                .find(|(_, release_year, release_week)| {
+                    (*release_year, *release_week) >= (year, week)
+                })
+                .map(|(release, _, _)| *release)
+                .unwrap_or((u8::MAX, u8::MAX, None)),
+            stage: 0,
+            number: (year, week, 0),
+            name,
+        };
+        let modern =
+            |major: u8, minor: u8, patch: Option<u8>, stage: u8, number: u8| VersionOrder::Modern {
+                release: (major, minor, patch),
+                stage,
+                number: (0, 0, number),
+                name: "",
+            };
+        match self {
+            MinecraftVersion::Release(major, minor, patch) => modern(*major, *minor, *patch, 3, 0),
+            MinecraftVersion::PreRelease(major, minor, patch, pre) => {
+                modern(*major, *minor, *patch, 1, *pre)
+            }
+            MinecraftVersion::ReleaseCandidate(major, minor, patch, rc) => {
+                modern(*major, *minor, *patch, 2, *rc)
+            }
+            MinecraftVersion::Snapshot(year, week, name) => snapshot(*year, *week, name),
+            MinecraftVersion::AprilFools(name) => {
+                let (_, year, week) = APRIL_FOOLS
+                    .iter()
+                    .find(|(id, _, _)| id == name)
+                    .copied()
+                    .unwrap_or(("", u8::MAX, u8::MAX));
+                snapshot(year, week, name)
+            }
+            MinecraftVersion::Beta(name) => VersionOrder::Beta(version_parts(name)),
+            MinecraftVersion::Alpha(name) => VersionOrder::Alpha(version_parts(name)),
+            MinecraftVersion::Infdev(name) => VersionOrder::Infdev(version_parts(name)),
+            MinecraftVersion::Classic(name) if name.starts_with("rd-") => {
+                VersionOrder::PreClassic(version_parts(name))
+            }
+            MinecraftVersion::Classic(name) => VersionOrder::Classic(version_parts(name)),
+            MinecraftVersion::Unknown(name) => VersionOrder::Unknown(name),
+        }
+    }
+}
+
+impl PartialOrd for MinecraftVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MinecraftVersion {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.order().cmp(&other.order())
     }
 }
 
@@ -397,7 +843,7 @@ pub struct Version {
     pub minecraft_arguments: Option<String>,
     pub arguments: Option<Arguments>,
     pub main_class: Option<String>,
-    pub libraries: Option<Vec<Value>>,
+    pub libraries: Option<Vec<Library>>,
     pub jar: Option<String>,
     pub asset_index: Option<AssetIndex>,
     pub assets: Option<String>,
@@ -431,41 +877,59 @@ impl Version {
             .join(format!("{}.json", version_name));
 
         let raw = read_to_string(path)?;
-        let version: Version = serde_json::from_str((&raw).as_ref())?;
+        let version: Version = serde_json::from_str(raw.as_ref())?;
         Ok(version)
     }
 
     /// parse a Minecraft version json
+    ///
+    /// The versions in the `inheritsFrom` chain are read from the versions folder and merged like
+    /// the vanilla launcher does: the arguments are concatenated, the libraries of the child
+    /// replace the libraries of the parent with the same `group:artifact[:classifier]`, and the
+    /// other fields of the child override the parent.
+    ///
+    /// Returns a [`VersionError`] if a parent is missing, the chain is circular, or the merged
+    /// version has no main class.
     pub async fn parse(
         &self,
         minecraft: &MinecraftLocation,
         platform: &PlatformInfo,
     ) -> Result<ResolvedVersion> {
+        let mut versions = vec![self.clone()];
+        let mut inheritances = vec![self.id.clone()];
+        let mut path_chain = vec![minecraft.get_version_json(&self.id)];
         let mut inherits_from = self.inherits_from.clone();
-        let versions_folder = &minecraft.versions;
-        let mut versions = Vec::new();
-        let mut inheritances = Vec::new();
-        let mut path_chain = Vec::new();
-        versions.push(self.clone());
-        while let Some(inherits_from_unwrap) = inherits_from {
-            inheritances.push(inherits_from_unwrap.clone());
-
-            let path = versions_folder
-                .join(inherits_from_unwrap.clone())
-                .join(format!("{}.json", inherits_from_unwrap.clone()));
-            path_chain.push(path.clone());
-            let version_json = read_to_string(path)?;
-            let version_json: Version = serde_json::from_str((&version_json).as_ref())?;
-
-            versions.push(version_json.clone());
-            inherits_from = version_json.inherits_from;
+        while let Some(parent) = inherits_from {
+            if inheritances.contains(&parent) {
+                inheritances.push(parent);
+                return Err(VersionError::CircularInheritance(inheritances).into());
+            }
+            let path = minecraft.get_version_json(&parent);
+            let version_json = match read_to_string(&path) {
+                Ok(version_json) => version_json,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    return Err(VersionError::MissingParent {
+                        id: inheritances.last().unwrap().clone(),
+                        parent,
+                    }
+                    .into());
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let version_json: Version = serde_json::from_str(version_json.as_ref())?;
+
+            inherits_from = version_json.inherits_from.clone();
+            inheritances.push(parent);
+            path_chain.push(path);
+            versions.push(version_json);
         }
 
         let mut assets = "".to_string();
         let mut minimum_launcher_version = 0;
 
-        let game_args = DEFAULT_GAME_ARGS.clone();
-        let jvm_args = DEFAULT_JVM_ARGS.clone();
+        let mut game_args = Vec::new();
+        let mut jvm_args = Vec::new();
+        let mut minecraft_arguments = None;
         let mut release_time = "".to_string();
         let mut time = "".to_string();
         let mut version_type = "".to_string();
@@ -476,11 +940,11 @@ impl Version {
             component: "jre-legacy".to_string(),
             major_version: 8,
         };
-        let mut libraries_raw = Vec::new();
+        let mut libraries: Vec<Library> = Vec::new();
         let mut downloads = HashMap::new();
 
-        while versions.len() != 0 {
-            let version = versions.pop().unwrap();
+        // from the root version to this version
+        while let Some(version) = versions.pop() {
             minimum_launcher_version = std::cmp::max(
                 version.minimum_launcher_version.unwrap_or(0),
                 minimum_launcher_version,
@@ -488,43 +952,48 @@ impl Version {
 
             release_time = version.release_time.unwrap_or(release_time);
             time = version.time.unwrap_or(time);
-            logging = if let Some(logging_) = version.logging {
-                if logging_.len() == 0 {
-                    logging
-                } else {
-                    logging_.clone()
+            if let Some(version_logging) = version.logging {
+                if !version_logging.is_empty() {
+                    logging = version_logging;
                 }
-            } else {
-                logging
-            };
+            }
             assets = version.assets.unwrap_or(assets);
             version_type = version.r#type.unwrap_or(version_type);
             main_class = version.main_class.unwrap_or(main_class);
-            asset_index = match version.asset_index {
-                Some(asset_index) => Some(asset_index),
-                None => asset_index,
-            };
+            asset_index = version.asset_index.or(asset_index);
             java_version = version.java_version.unwrap_or(java_version);
+            minecraft_arguments = version.minecraft_arguments.or(minecraft_arguments);
+            if let Some(arguments) = version.arguments {
+                game_args.extend(arguments.game.unwrap_or_default());
+                jvm_args.extend(arguments.jvm.unwrap_or_default());
+            }
 
-            if let Some(libraries) = version.libraries {
-                libraries_raw.splice(0..0, libraries);
+            if let Some(version_libraries) = version.libraries {
+                let keys: Vec<String> = version_libraries.iter().map(Library::key).collect();
+                libraries.retain(|library| !keys.contains(&library.key()));
+                libraries.splice(0..0, version_libraries);
             }
-            match version.downloads {
-                Some(v) => downloads.extend(v),
-                None => (),
+            if let Some(v) = version.downloads {
+                downloads.extend(v)
             };
         }
-        let main_class_is_empty = main_class.is_empty();
-        let assets_index_is_empty = asset_index
-            == Some(AssetIndex {
-                size: 0,
-                url: "".to_string(),
-                id: "".to_string(),
-                total_size: 0,
-            });
-        let downloads_is_empty = downloads.len() == 0;
-        if main_class_is_empty || assets_index_is_empty || downloads_is_empty {
-            return Err(anyhow::anyhow!("Bad Version JSON"));
+        // versions before 1.13 only have `minecraftArguments`, and the child version repeats the
+        // whole string of its parent, so the last one wins
+        if game_args.is_empty() {
+            game_args = match minecraft_arguments {
+                Some(arguments) => arguments.split_whitespace().map(Argument::from).collect(),
+                None => DEFAULT_GAME_ARGS.iter().map(Argument::from).collect(),
+            };
+        }
+        if jvm_args.is_empty() {
+            jvm_args = DEFAULT_JVM_ARGS.iter().map(Argument::from).collect();
+        }
+        if main_class.is_empty() {
+            return Err(VersionError::BadVersionJson {
+                id: self.id.clone(),
+                reason: "mainClass is missing".to_string(),
+            }
+            .into());
         }
         Ok(ResolvedVersion {
             id: self.id.clone(),
@@ -536,106 +1005,237 @@ impl Version {
             asset_index,
             assets,
             downloads: Some(downloads),
-            libraries: resolve_libraries(libraries_raw, platform).await,
+            libraries: resolve_libraries(libraries, platform).await,
             minimum_launcher_version,
             release_time,
             time,
             version_type,
             logging: Some(logging),
-            java_version: self.java_version.clone().unwrap_or(JavaVersion {
-                component: "jre-legacy".to_string(),
-                major_version: 8,
-            }),
+            java_version,
             inheritances,
             path_chain,
         })
     }
 }
 
+/// Errors when resolving a version.json
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionError {
+    /// The version `id` inherits from `parent`, but `parent` is not installed.
+    MissingParent {
+        id: String,
+        parent: String,
+    },
+
+    /// The `inheritsFrom` chain goes back to a version already in the chain.
+    CircularInheritance(Vec<String>),
+    BadVersionJson {
+        id: String,
+        reason: String,
+    },
+
+    /// The version is not in the version manifest.
+    UnknownVersion(String),
+}
+
+impl std::fmt::Display for VersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionError::MissingParent { id, parent } => {
+                write!(
+                    f,
+                    "Version {id} inherits from {parent}, which is not installed"
+                )
+            }
+            VersionError::CircularInheritance(chain) => {
+                write!(f, "Circular inheritance: {}", chain.join(" -> "))
+            }
+            VersionError::BadVersionJson { id, reason } => {
+                write!(f, "Bad version json of {id}: {reason}")
+            }
+            VersionError::UnknownVersion(id) => {
+                write!(f, "Version {id} is not in the version manifest")
+            }
+        }
+    }
+}
+
+impl std::error::Error for VersionError {}
+
+/// The arguments of the whole inheritance chain.
+///
+/// The rules of the arguments are kept, because the enabled features are only known at launch,
+/// use `ResolvedArguments::game()` and `ResolvedArguments::jvm()` to get the final arguments.
 #[derive(Debug, Clone, Serialize)]
 pub struct ResolvedArguments {
-    pub game: Vec<String>,
-    pub jvm: Vec<String>,
+    pub game: Vec<Argument>,
+    pub jvm: Vec<Argument>,
+}
+
+impl ResolvedArguments {
+    /// The game arguments allowed on the platform with the enabled features.
+    pub fn game(&self, platform: &PlatformInfo, features: &HashMap<String, bool>) -> Vec<String> {
+        filter_arguments(&self.game, platform, features)
+    }
+
+    /// The jvm arguments allowed on the platform with the enabled features.
+    pub fn jvm(&self, platform: &PlatformInfo, features: &HashMap<String, bool>) -> Vec<String> {
+        filter_arguments(&self.jvm, platform, features)
+    }
+}
+
+fn filter_arguments(
+    arguments: &[Argument],
+    platform: &PlatformInfo,
+    features: &HashMap<String, bool>,
+) -> Vec<String> {
+    arguments
+        .iter()
+        .filter(|argument| check_allowed(&argument.rules, platform, features))
+        .flat_map(|argument| argument.value.clone())
+        .collect()
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ResolvedLibrary {
     pub download_info: LibraryDownload,
+
+    /// The library should be extracted to the natives folder instead of being on the classpath.
     pub is_native_library: bool,
+
+    /// The entries not to extract from a native library, like `META-INF/`.
+    pub extract_exclude: Vec<String>,
+}
+
+/// Get the os and the arch of a native artifact from its classifier.
+///
+/// Since 1.19, natives are separate artifacts with classifiers like `natives-linux`,
+/// `natives-windows-x86` or `natives-macos-arm64`, where no arch means x64.
+fn native_classifier_platform(name: &str) -> Option<(&'static str, &'static str)> {
+    let classifier = name.split(':').nth(3)?.strip_prefix("natives-")?;
+    let (os, arch) = classifier.split_once('-').unwrap_or((classifier, "x64"));
+    let os = match os {
+        "windows" => "windows",
+        "linux" => "linux",
+        "macos" | "osx" => "osx",
+        _ => return None,
+    };
+    Some((os, normalize_arch(arch)?))
+}
+
+/// Unify the arch names used by Mojang, LWJGL and `PlatformInfo`.
+pub(crate) fn normalize_arch(arch: &str) -> Option<&'static str> {
+    match arch {
+        "x64" | "x86_64" | "amd64" => Some("x64"),
+        "x86" | "i386" => Some("x86"),
+        "arm64" | "aarch64" => Some("aarch64"),
+        "arm32" | "arm" => Some("arm"),
+        _ => None,
+    }
+}
+
+/// Resolve the classifier in `natives` for the platform, the scheme used before 1.19.
+fn resolve_native_classifier(
+    library: &Library,
+    platform: &PlatformInfo,
+    bits: &str,
+) -> Option<ResolvedLibrary> {
+    let classifier_key = library
+        .natives
+        .as_ref()?
+        .get(&platform.name)?
+        .replace("${arch}", bits);
+    let classifier = library
+        .downloads
+        .as_ref()?
+        .classifiers
+        .as_ref()?
+        .get(&classifier_key)?;
+    Some(ResolvedLibrary {
+        download_info: classifier.clone(),
+        is_native_library: true,
+        extract_exclude: library
+            .extract
+            .as_ref()
+            .map(|extract| extract.exclude.clone())
+            .unwrap_or_default(),
+    })
 }
 
-async fn resolve_libraries(libraries: Vec<Value>, platform: &PlatformInfo) -> Vec<ResolvedLibrary> {
+async fn resolve_libraries(
+    libraries: Vec<Library>,
+    platform: &PlatformInfo,
+) -> Vec<ResolvedLibrary> {
+    let platform_arch = normalize_arch(&platform.arch).unwrap_or("x64");
+    // artifacts with a native classifier for the current platform, the x64 one is only a
+    // fallback when there is no artifact for the current arch
+    let native_artifacts: Vec<(&str, &str)> = libraries
+        .iter()
+        .filter_map(|library| match native_classifier_platform(&library.name) {
+            Some((os, arch)) if os == platform.name => {
+                Some((library.name.rsplit_once(':').unwrap().0, arch))
+            }
+            _ => None,
+        })
+        .collect();
+    // `${arch}` in the natives keys is the bits of the jvm
+    let bits = match platform_arch {
+        "x86" | "arm" => "32",
+        _ => "64",
+    };
+
     let mut result = Vec::new();
-    for library in libraries {
-        let rules = library["rules"].as_array();
+    for library in &libraries {
         // check rules
-        if let Some(rules) = rules {
-            if !check_allowed(rules.clone(), &platform) {
+        if let Some(rules) = &library.rules {
+            if !check_allowed(rules, platform, &HashMap::new()) {
                 continue;
             }
         }
+        let artifact = library
+            .downloads
+            .as_ref()
+            .and_then(|downloads| downloads.artifact.clone());
         // resolve native lib
-        let classifiers = library["downloads"]["classifiers"].as_object();
-        let natives = library["natives"].as_object();
-        if classifiers.is_some() && natives.is_some() {
-            let classifiers = classifiers.unwrap();
-            let natives = natives.unwrap();
-            let classifier_key = match natives[&platform.name].as_str() {
-                None => continue,
-                Some(x) => x,
-            };
-            let classifier = match classifiers[classifier_key].as_object() {
-                None => continue,
-                Some(x) => x,
-            };
-            let url = match classifier["url"].as_str() {
-                Some(url) => url.to_string(),
-                None => continue,
-            };
-            let path = match classifier["path"].as_str() {
-                Some(path) => path.to_string(),
-                None => continue,
-            };
-            result.push(ResolvedLibrary {
-                download_info: LibraryDownload {
-                    sha1: match classifier["sha1"].as_str() {
-                        Some(sha1) => Some(sha1.to_string()),
-                        None => None,
-                    },
-                    size: classifier["size"].as_u64(),
-                    url,
-                    path,
-                },
-                is_native_library: true,
-            });
+        if library.natives.is_some() {
+            if let Some(native) = resolve_native_classifier(library, platform, bits) {
+                result.push(native);
+            }
+            if artifact.is_none() {
+                continue;
+            }
         }
         // resolve common lib
-        if library["downloads"]["artifact"].is_object() {
+        if let Some(artifact) = artifact {
+            if let Some((os, arch)) = native_classifier_platform(&library.name) {
+                let name = library.name.rsplit_once(':').unwrap().0;
+                let has_platform_arch = native_artifacts.contains(&(name, platform_arch));
+                let allowed = os == platform.name
+                    && (arch == platform_arch || (arch == "x64" && !has_platform_arch));
+                if !allowed {
+                    continue;
+                }
+            }
             result.push(ResolvedLibrary {
-                download_info: serde_json::from_value(library["downloads"]["artifact"].clone())
-                    .unwrap(),
+                download_info: artifact,
                 is_native_library: false,
+                extract_exclude: vec![],
             });
             continue;
         }
         // resolve mod loader
-        let name = match library["name"].as_str() {
-            None => continue,
-            Some(x) => x,
-        };
-        let name: Vec<&str> = name.split(":").collect();
+        let name: Vec<&str> = library.name.split(":").collect();
         if name.len() != 3 {
             continue;
         }
-        let package = name.get(0).unwrap().replace(".", "/");
+        let package = name.first().unwrap().replace(".", "/");
         let version = name.get(2).unwrap();
         let name = name.get(1).unwrap();
 
-        let url = if let Some(url) = library["url"].as_str() {
-            url
-        } else {
-            "https://libraries.minecraft.net/"
-        };
+        let url = library
+            .url
+            .as_deref()
+            .unwrap_or("https://libraries.minecraft.net/");
         let path = format!("{package}/{name}/{version}/{name}-{version}.jar");
         result.push(ResolvedLibrary {
             download_info: LibraryDownload {
@@ -645,50 +1245,29 @@ async fn resolve_libraries(libraries: Vec<Value>, platform: &PlatformInfo) -> Ve
                 path,
             },
             is_native_library: false,
+            extract_exclude: vec![],
         });
     }
     result
 }
 
 /// Check if all the rules in Rule[] are acceptable in certain OS platform and features.
-fn check_allowed(rules: Vec<Value>, platform: &PlatformInfo) -> bool {
-    // by default it's allowed
+///
+/// Same as the vanilla launcher, no rule means allowed, otherwise it's disallowed unless a rule
+/// matches, and the last matched rule wins.
+pub fn check_allowed(
+    rules: &[Rule],
+    platform: &PlatformInfo,
+    features: &HashMap<String, bool>,
+) -> bool {
     if rules.is_empty() {
         return true;
     }
-    // else it's disallow by default
-    let mut allow = false;
-    for rule in rules {
-        let action = rule["action"].as_str().unwrap() == "allow";
-        let os = rule["os"].clone();
-        if !os.is_object() {
-            allow = action;
-            continue;
-        }
-        if !os["name"].is_string() {
-            allow = action;
-            continue;
-        }
-        if platform.name != os["name"].as_str().unwrap() {
-            continue;
-        }
-        if os["features"].is_object() {
-            return false;
-        }
-        if !os["version"].is_string() {
-            allow = action;
-            continue;
-        }
-        let version = os["version"].as_str().unwrap();
-        if Regex::is_match(
-            &Regex::new(version).unwrap(),
-            (&platform.version.to_string()).as_ref(),
-        ) {
-            allow = action;
-        }
-        // todo: check `features`
-    }
-    allow
+    rules
+        .iter()
+        .rfind(|rule| rule.is_matched(platform, features))
+        .map(|rule| rule.action == "allow")
+        .unwrap_or(false)
 }
 
 pub struct LibraryInfo {
@@ -714,21 +1293,30 @@ impl LibraryInfo {
     /// Get the base info of the library from its name
     /// * `lib` - The name of library of the library itself
     pub fn from_value(lib: &Value) -> Self {
-        let name = lib["name"].as_str().unwrap().to_string();
+        Self::from_name(lib["name"].as_str().unwrap())
+    }
+
+    /// Get the base info of the library from its maven name
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_string();
         let split_name = name.split("@").collect::<Vec<&str>>();
-        let body = split_name.get(0).unwrap().split(":").collect::<Vec<&str>>();
+        let body = split_name
+            .first()
+            .unwrap()
+            .split(":")
+            .collect::<Vec<&str>>();
         let r#type = split_name.get(1).unwrap_or(&"jar").to_string();
-        let group_id = body.get(0).unwrap().to_string();
+        let group_id = body.first().unwrap().to_string();
         let artifact_id = body.get(1).unwrap().to_string();
         let version = body.get(2).unwrap().to_string();
         let is_snapshot = version.ends_with("SNAPSHOT");
         let group_path = group_id.replace(".", "/");
         let base = format!("{group_path}/{artifact_id}/{version}/{artifact_id}-{version}");
-        let classifier = match body.get(3) {
-            Some(classifier) => format!("{base}-{classifier}"),
-            None => "".to_string(),
+        let classifier = body.get(3).unwrap_or(&"").to_string();
+        let path = match classifier.as_str() {
+            "" => format!("{base}.{type}"),
+            classifier => format!("{base}-{classifier}.{type}"),
         };
-        let path = format!("{base}.{type}");
         Self {
             group_id,
             artifact_id,
@@ -741,3 +1329,566 @@ impl LibraryInfo {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::core::OsType;
+    use crate::utils::test_server::{self, Response};
+
+    fn linux() -> PlatformInfo {
+        PlatformInfo {
+            arch: "x64".to_string(),
+            name: "linux".to_string(),
+            os_type: OsType::Linux,
+            version: "6.1.0\n".to_string(),
+        }
+    }
+
+    fn write_version(minecraft: &MinecraftLocation, version: &Value) -> Version {
+        let id = version["id"].as_str().unwrap();
+        let folder = minecraft.versions.join(id);
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join(format!("{id}.json")), version.to_string()).unwrap();
+        Version::from_value(version.clone()).unwrap()
+    }
+
+    fn vanilla(id: &str) -> Value {
+        json!({
+            "id": id,
+            "mainClass": "net.minecraft.client.main.Main",
+            "assetIndex": {"id": "5", "sha1": "", "size": 1, "totalSize": 1, "url": "https://a/5.json"},
+            "downloads": {"client": {"sha1": "", "size": 1, "url": "https://a/client.jar"}},
+            "libraries": [],
+        })
+    }
+
+    #[tokio::test]
+    async fn resolve_arguments_across_inheritance() {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let mut parent = vanilla("1.20.1");
+        parent["arguments"] = json!({
+            "game": [
+                "--username", "${auth_player_name}",
+                {"rules": [{"action": "allow", "features": {"is_demo_user": true}}], "value": "--demo"},
+                {"rules": [{"action": "allow", "features": {"has_custom_resolution": true}}],
+                 "value": ["--width", "${resolution_width}"]},
+            ],
+            "jvm": [
+                {"rules": [{"action": "allow", "os": {"name": "osx"}}], "value": ["-XstartOnFirstThread"]},
+                {"rules": [{"action": "allow", "os": {"name": "linux"}}], "value": "-Dlinux=true"},
+                {"rules": [{"action": "allow", "os": {"arch": "x86"}}], "value": "-Xss1M"},
+                "-cp", "${classpath}",
+            ],
+        });
+        write_version(&minecraft, &parent);
+        let child = write_version(
+            &minecraft,
+            &json!({
+                "id": "1.20.1-forge",
+                "inheritsFrom": "1.20.1",
+                "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+                "arguments": {
+                    "game": ["--launchTarget", "forgeclient"],
+                    "jvm": ["-p", "${library_directory}/a.jar${classpath_separator}${library_directory}/b.jar"],
+                },
+            }),
+        );
+
+        let arguments = child
+            .parse(&minecraft, &linux())
+            .await
+            .unwrap()
+            .arguments
+            .unwrap();
+        let features = HashMap::new();
+        assert_eq!(
+            arguments.game(&linux(), &features),
+            [
+                "--username",
+                "${auth_player_name}",
+                "--launchTarget",
+                "forgeclient"
+            ]
+        );
+        assert_eq!(
+            arguments.jvm(&linux(), &features),
+            [
+                "-Dlinux=true",
+                "-cp",
+                "${classpath}",
+                "-p",
+                "${library_directory}/a.jar${classpath_separator}${library_directory}/b.jar",
+            ]
+        );
+
+        let features = HashMap::from([
+            ("has_custom_resolution".to_string(), true),
+            ("is_demo_user".to_string(), false),
+        ]);
+        assert_eq!(
+            arguments.game(&linux(), &features),
+            [
+                "--username",
+                "${auth_player_name}",
+                "--width",
+                "${resolution_width}",
+                "--launchTarget",
+                "forgeclient",
+            ]
+        );
+        let x86 = PlatformInfo {
+            arch: "x86".to_string(),
+            ..linux()
+        };
+        assert!(arguments
+            .jvm(&x86, &features)
+            .contains(&"-Xss1M".to_string()));
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn resolve_legacy_arguments() {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let mut parent = vanilla("1.12.2");
+        parent["minecraftArguments"] =
+            json!("--username ${auth_player_name} --version ${version_name}");
+        write_version(&minecraft, &parent);
+        let child = write_version(
+            &minecraft,
+            &json!({
+                "id": "1.12.2-forge",
+                "inheritsFrom": "1.12.2",
+                "minecraftArguments": "--username  ${auth_player_name} --version ${version_name} --tweakClass net.minecraftforge.fml.common.launcher.FMLTweaker",
+            }),
+        );
+
+        let arguments = child
+            .parse(&minecraft, &linux())
+            .await
+            .unwrap()
+            .arguments
+            .unwrap();
+        assert_eq!(
+            arguments.game(&linux(), &HashMap::new()),
+            [
+                "--username",
+                "${auth_player_name}",
+                "--version",
+                "${version_name}",
+                "--tweakClass",
+                "net.minecraftforge.fml.common.launcher.FMLTweaker",
+            ]
+        );
+        assert_eq!(arguments.jvm(&linux(), &HashMap::new()), *DEFAULT_JVM_ARGS);
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
+    }
+
+    #[test]
+    fn check_rules() {
+        let rules: Vec<Rule> = serde_json::from_value(json!([
+            {"action": "allow"},
+            {"action": "disallow", "os": {"name": "osx"}},
+        ]))
+        .unwrap();
+        let osx = PlatformInfo {
+            name: "osx".to_string(),
+            os_type: OsType::Osx,
+            ..linux()
+        };
+        assert!(check_allowed(&rules, &linux(), &HashMap::new()));
+        assert!(!check_allowed(&rules, &osx, &HashMap::new()));
+
+        let rules: Vec<Rule> = serde_json::from_value(json!([
+            {"action": "allow", "os": {"name": "windows", "version": "^10\\."}},
+        ]))
+        .unwrap();
+        let windows = |version: &str| PlatformInfo {
+            name: "windows".to_string(),
+            os_type: OsType::Windows,
+            version: version.to_string(),
+            ..linux()
+        };
+        assert!(check_allowed(
+            &rules,
+            &windows("10.0.19045"),
+            &HashMap::new()
+        ));
+        assert!(!check_allowed(
+            &rules,
+            &windows("6.1.7601"),
+            &HashMap::new()
+        ));
+        assert!(!check_allowed(&rules, &linux(), &HashMap::new()));
+
+        let rules: Vec<Rule> = serde_json::from_value(json!([
+            {"action": "allow", "features": {"is_quick_play_singleplayer": true}},
+        ]))
+        .unwrap();
+        let features = HashMap::from([("is_quick_play_singleplayer".to_string(), true)]);
+        assert!(check_allowed(&rules, &linux(), &features));
+        assert!(!check_allowed(&rules, &linux(), &HashMap::new()));
+        assert!(check_allowed(&[], &linux(), &HashMap::new()));
+    }
+
+    #[tokio::test]
+    async fn resolve_native_libraries() {
+        let artifact = |path: &str| json!({"path": path, "sha1": "", "size": 1, "url": format!("https://a/{path}")});
+        let libraries = vec![
+            // before 1.19, natives in classifiers
+            json!({
+                "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4",
+                "downloads": {"classifiers": {
+                    "natives-linux": artifact("lwjgl-platform-linux.jar"),
+                    "natives-windows-32": artifact("lwjgl-platform-windows-32.jar"),
+                    "natives-windows-64": artifact("lwjgl-platform-windows-64.jar"),
+                }},
+                "extract": {"exclude": ["META-INF/"]},
+                "natives": {"linux": "natives-linux", "windows": "natives-windows-${arch}"},
+            }),
+            // since 1.19, natives are artifacts
+            json!({"name": "org.lwjgl:lwjgl:3.3.1", "downloads": {"artifact": artifact("lwjgl.jar")}}),
+            json!({
+                "name": "org.lwjgl:lwjgl:3.3.1:natives-linux",
+                "downloads": {"artifact": artifact("lwjgl-natives-linux.jar")},
+                "rules": [{"action": "allow", "os": {"name": "linux"}}],
+            }),
+            json!({
+                "name": "org.lwjgl:lwjgl:3.3.1:natives-windows",
+                "downloads": {"artifact": artifact("lwjgl-natives-windows.jar")},
+                "rules": [{"action": "allow", "os": {"name": "windows"}}],
+            }),
+            json!({
+                "name": "org.lwjgl:lwjgl:3.3.1:natives-windows-x86",
+                "downloads": {"artifact": artifact("lwjgl-natives-windows-x86.jar")},
+                "rules": [{"action": "allow", "os": {"name": "windows"}}],
+            }),
+            json!({
+                "name": "org.lwjgl:lwjgl:3.3.1:natives-linux-arm64",
+                "downloads": {"artifact": artifact("lwjgl-natives-linux-arm64.jar")},
+                "rules": [{"action": "allow", "os": {"name": "linux", "arch": "arm64"}}],
+            }),
+        ];
+        let libraries: Vec<Library> = serde_json::from_value(Value::Array(libraries)).unwrap();
+        let resolve = |platform: PlatformInfo| {
+            let libraries = libraries.clone();
+            async move {
+                resolve_libraries(libraries, &platform)
+                    .await
+                    .into_iter()
+                    .map(|library| {
+                        (
+                            library.download_info.path,
+                            library.is_native_library,
+                            library.extract_exclude,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+        let no_exclude: Vec<String> = vec![];
+
+        assert_eq!(
+            resolve(linux()).await,
+            [
+                (
+                    "lwjgl-platform-linux.jar".to_string(),
+                    true,
+                    vec!["META-INF/".to_string()]
+                ),
+                ("lwjgl.jar".to_string(), false, no_exclude.clone()),
+                (
+                    "lwjgl-natives-linux.jar".to_string(),
+                    false,
+                    no_exclude.clone()
+                ),
+            ]
+        );
+        let aarch64 = PlatformInfo {
+            arch: "aarch64".to_string(),
+            ..linux()
+        };
+        assert_eq!(
+            resolve(aarch64).await[1..],
+            [
+                ("lwjgl.jar".to_string(), false, no_exclude.clone()),
+                (
+                    "lwjgl-natives-linux-arm64.jar".to_string(),
+                    false,
+                    no_exclude.clone()
+                ),
+            ]
+        );
+        let windows_x86 = PlatformInfo {
+            arch: "x86".to_string(),
+            name: "windows".to_string(),
+            os_type: OsType::Windows,
+            version: "10.0.19045".to_string(),
+        };
+        assert_eq!(
+            resolve(windows_x86).await,
+            [
+                (
+                    "lwjgl-platform-windows-32.jar".to_string(),
+                    true,
+                    vec!["META-INF/".to_string()]
+                ),
+                ("lwjgl.jar".to_string(), false, no_exclude.clone()),
+                (
+                    "lwjgl-natives-windows-x86.jar".to_string(),
+                    false,
+                    no_exclude.clone()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_minecraft_versions() {
+        use MinecraftVersion::*;
+        let cases = [
+            ("1.20.1", Release(1, 20, Some(1))),
+            ("1.8", Release(1, 8, None)),
+            ("1.20-pre1", PreRelease(1, 20, None, 1)),
+            ("1.14 Pre-Release 2", PreRelease(1, 14, None, 2)),
+            ("1.20.1-rc1", ReleaseCandidate(1, 20, Some(1), 1)),
+            ("23w13a", Snapshot(23, 13, "a".to_string())),
+            ("23w13a_or_b", Snapshot(23, 13, "a_or_b".to_string())),
+            ("1.RV-Pre1", AprilFools("1.RV-Pre1".to_string())),
+            (
+                "3D Shareware v1.34",
+                AprilFools("3D Shareware v1.34".to_string()),
+            ),
+            ("2.0", AprilFools("2.0".to_string())),
+            ("b1.7.3", Beta("b1.7.3".to_string())),
+            ("a1.0.4", Alpha("a1.0.4".to_string())),
+            ("inf-20100618", Infdev("inf-20100618".to_string())),
+            ("c0.30_01c", Classic("c0.30_01c".to_string())),
+            ("rd-132211", Classic("rd-132211".to_string())),
+            (
+                "1.14_combat-212796",
+                Unknown("1.14_combat-212796".to_string()),
+            ),
+        ];
+        for (id, version) in cases {
+            assert_eq!(MinecraftVersion::from_str(id).unwrap(), version, "{id}");
+        }
+    }
+
+    #[test]
+    fn order_minecraft_versions() {
+        let ordered = [
+            "rd-132211",
+            "rd-160052",
+            "c0.0.11a",
+            "c0.30_01c",
+            "inf-20100618",
+            "a1.0.4",
+            "a1.2.6",
+            "b1.7.3",
+            "b1.8.1",
+            "1.0",
+            "1.2.5",
+            "2.0",
+            "1.6.4",
+            "1.7.10",
+            "1.8.9",
+            "15w14a",
+            "1.9",
+            "1.RV-Pre1",
+            "1.9.4",
+            "17w43a",
+            "1.13-pre1",
+            "1.13",
+            "3D Shareware v1.34",
+            "1.14 Pre-Release 1",
+            "1.14",
+            "1.14.4",
+            "1.19.4",
+            "23w13a",
+            "23w13a_or_b",
+            "1.20-pre1",
+            "1.20-rc1",
+            "1.20",
+            "1.20.1-rc1",
+            "1.20.1",
+            "24w14potato",
+            "1.20.5",
+            "1.21.10",
+            "99w01a",
+        ];
+        let versions: Vec<MinecraftVersion> =
+            ordered.iter().map(|id| id.parse().unwrap()).collect();
+        for (i, pair) in versions.windows(2).enumerate() {
+            assert!(pair[0] < pair[1], "{} < {}", ordered[i], ordered[i + 1]);
+        }
+        let mut shuffled = versions.clone();
+        shuffled.reverse();
+        shuffled.sort();
+        assert_eq!(shuffled, versions);
+    }
+
+    #[tokio::test]
+    async fn merge_inherited_versions() {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let library = |name: &str| json!({"name": name, "url": "https://maven.example.com/"});
+        let mut parent = vanilla("1.20.1");
+        parent["javaVersion"] = json!({"component": "java-runtime-gamma", "majorVersion": 17});
+        parent["logging"] = json!({"client": {
+            "argument": "-Dlog4j.configurationFile=${path}",
+            "file": {"id": "client-1.12.xml", "sha1": "", "size": 1, "url": "https://a/client.xml"},
+            "type": "log4j2-xml",
+        }});
+        parent["libraries"] = json!([
+            library("org.ow2.asm:asm:9.3"),
+            library("org.lwjgl:lwjgl:3.3.1"),
+            library("org.lwjgl:lwjgl:3.3.1:natives-linux"),
+        ]);
+        write_version(&minecraft, &parent);
+        let child = write_version(
+            &minecraft,
+            &json!({
+                "id": "fabric-loader-0.14.21-1.20.1",
+                "inheritsFrom": "1.20.1",
+                "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+                "libraries": [library("org.ow2.asm:asm:9.5"), library("net.fabricmc:fabric-loader:0.14.21")],
+            }),
+        );
+
+        let resolved = child.parse(&minecraft, &linux()).await.unwrap();
+        assert_eq!(
+            resolved.main_class,
+            "net.fabricmc.loader.impl.launch.knot.KnotClient"
+        );
+        assert_eq!(resolved.java_version.major_version, 17);
+        assert!(resolved.logging.unwrap().contains_key("client"));
+        assert_eq!(resolved.asset_index.unwrap().id, "5");
+        assert_eq!(
+            resolved.inheritances,
+            ["fabric-loader-0.14.21-1.20.1", "1.20.1"]
+        );
+        assert_eq!(
+            resolved
+                .libraries
+                .iter()
+                .map(|library| library.download_info.path.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "org/ow2/asm/asm/9.5/asm-9.5.jar",
+                "net/fabricmc/fabric-loader/0.14.21/fabric-loader-0.14.21.jar",
+                "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar",
+            ]
+        );
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn inheritance_errors() {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let missing = write_version(&minecraft, &json!({"id": "a", "inheritsFrom": "1.20.1"}));
+        let error = missing.parse(&minecraft, &linux()).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<VersionError>(),
+            Some(&VersionError::MissingParent {
+                id: "a".to_string(),
+                parent: "1.20.1".to_string()
+            })
+        );
+
+        let circular = write_version(&minecraft, &json!({"id": "b", "inheritsFrom": "c"}));
+        write_version(&minecraft, &json!({"id": "c", "inheritsFrom": "b"}));
+        let error = circular.parse(&minecraft, &linux()).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<VersionError>(),
+            Some(&VersionError::CircularInheritance(vec![
+                "b".to_string(),
+                "c".to_string(),
+                "b".to_string()
+            ]))
+        );
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
+    }
+
+    /// Serve the manifest with an `ETag`, and `304 Not Modified` when the request has it.
+    async fn serve_manifest(body: String) -> (String, Arc<AtomicUsize>) {
+        let not_modified = Arc::new(AtomicUsize::new(0));
+        let not_modified_clone = not_modified.clone();
+        let base = test_server::serve(move |request| {
+            if request.header("if-none-match") == Some("\"v1\"") {
+                not_modified_clone.fetch_add(1, Ordering::SeqCst);
+                Response::new(304, Vec::new())
+            } else {
+                Response::new(200, body.clone()).header("ETag", "\"v1\"")
+            }
+        })
+        .await;
+        (format!("{base}/version_manifest_v2.json"), not_modified)
+    }
+
+    #[tokio::test]
+    async fn cache_version_manifest() {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let manifest = json!({
+            "latest": {"release": "1.20.1", "snapshot": "1.20.1"},
+            "versions": [{
+                "id": "1.20.1",
+                "type": "release",
+                "url": "https://piston-meta.mojang.com/v1/packages/715ccf3330885e75b205124f09f8712542cbe7e0/1.20.1.json",
+                "time": "2023-06-12T13:25:51+00:00",
+                "releaseTime": "2023-06-12T13:25:51+00:00",
+                "sha1": "715ccf3330885e75b205124f09f8712542cbe7e0",
+                "complianceLevel": 1
+            }]
+        });
+        let (url, not_modified) = serve_manifest(manifest.to_string()).await;
+
+        let fetched = VersionManifest::cached_from(&url, &minecraft)
+            .await
+            .unwrap();
+        assert_eq!(fetched.find("1.20.1").unwrap().id, "1.20.1");
+        assert!(minecraft.get_version_manifest().is_file());
+        assert_eq!(not_modified.load(Ordering::SeqCst), 0);
+
+        // revalidated with the etag
+        let revalidated = VersionManifest::cached_from(&url, &minecraft)
+            .await
+            .unwrap();
+        assert_eq!(revalidated, fetched);
+        assert_eq!(not_modified.load(Ordering::SeqCst), 1);
+
+        // offline
+        let offline = {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            format!(
+                "http://{}/version_manifest_v2.json",
+                listener.local_addr().unwrap()
+            )
+        };
+        let cached = VersionManifest::cached_from(&offline, &minecraft)
+            .await
+            .unwrap();
+        assert_eq!(cached, fetched);
+        assert_eq!(
+            cached.find("1.0.0").unwrap_err(),
+            VersionError::UnknownVersion("1.0.0".to_string())
+        );
+
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
+        assert!(VersionManifest::cached_from(&offline, &minecraft)
+            .await
+            .is_err());
+    }
+}
@@ -92,7 +92,7 @@ impl Parse for FabricModMetadata {
     fn parse(self) -> ResolvedMod {
         let name = match self.name {
             Some(v) => v,
-            None => self.id,
+            None => self.id.clone(),
         };
         let mut minecraft_depend = None;
         let mut fabric_loader_depend = None;
@@ -115,7 +115,7 @@ impl Parse for FabricModMetadata {
                     license
                         .as_array()
                         .unwrap()
-                        .into_iter()
+                        .iter()
                         .map(|v| v.as_str().unwrap().to_string())
                         .collect::<Vec<String>>(),
                 )
@@ -154,6 +154,7 @@ impl Parse for FabricModMetadata {
             );
         }
         ResolvedMod {
+            id: Some(self.id),
             name,
             description: self.description,
             version: Some(self.version.clone()),
@@ -162,19 +163,20 @@ impl Parse for FabricModMetadata {
                 mod_loader: fabric_loader_depend,
                 java: java_depend,
             },
-            authors: match parsed_authors {
-                Some(v) => v,
-                None => vec![]
-            },
+            authors: parsed_authors.unwrap_or_default(),
             license,
             icon: self.icon,
+            file: None,
         }
     }
 }
 
 pub fn parse_mod<P: AsRef<Path>>(path: P) -> Result<ResolvedMod> {
-    let metadata = FabricModMetadata::from_path(path)?;
-    Ok(metadata.parse())
+    let metadata = FabricModMetadata::from_path(&path)?;
+    Ok(ResolvedMod {
+        file: Some(path.as_ref().to_path_buf()),
+        ..metadata.parse()
+    })
 }
 
 pub fn parse_folder<S: AsRef<OsStr> + ?Sized>(
@@ -192,11 +194,14 @@ pub fn parse_folder<S: AsRef<OsStr> + ?Sized>(
         if path.is_dir() {
             continue;
         }
-        let raw_metadata = match FabricModMetadata::from_path(path) {
+        let raw_metadata = match FabricModMetadata::from_path(&path) {
             Ok(v) => v,
             Err(_) => continue
         };
-        result.push(raw_metadata.parse());
+        result.push(ResolvedMod {
+            file: Some(path),
+            ..raw_metadata.parse()
+        });
     }
     Ok(result)
 }
@@ -18,6 +18,7 @@
 
 use std::fs::File;
 use std::path::Path;
+use std::str::FromStr;
 use std::{ffi::OsStr, io::Read};
 
 use anyhow::Result;
@@ -58,9 +59,10 @@ pub struct ForgeModMcmodInfo {
 impl Parse for ForgeModMcmodInfo {
     fn parse(self) -> ResolvedMod {
         ResolvedMod {
+            id: self.mod_id.clone(),
             name: match self.name {
                 Some(v) => v,
-                None => match self.mod_id {
+                None => match self.mod_id.clone() {
                     Some(v) => v,
                     None => "".to_string(),
                 },
@@ -81,6 +83,7 @@ impl Parse for ForgeModMcmodInfo {
             version: self.version,
             icon: self.logo_file,
             license: None,
+            file: None,
             depends: {
                 match self.mcversion {
                     Some(v) => ResolvedDepends {
@@ -114,6 +117,8 @@ pub struct ForgeModTOMLMod {
 /// The `mods.toml` file is formatted as TOML, the example mods.toml file in the MDK provides
 /// comments explaining the contents of the file. It should be stored as
 /// src/main/resources/META-INF/mods.toml. A basic mods.toml, describing one mod, may look like this:
+///
+/// NeoForge names it `META-INF/neoforge.mods.toml` since 20.5, with the same format.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ForgeModTOMLData {
@@ -133,14 +138,16 @@ pub struct ForgeModTOMLData {
     pub version: Option<String>,
 }
 
-impl ForgeModTOMLData {
-    pub fn from_str(str: &str) -> Result<ForgeModTOMLData> {
+impl FromStr for ForgeModTOMLData {
+    type Err = anyhow::Error;
+
+    fn from_str(str: &str) -> Result<ForgeModTOMLData> {
         let raw: ForgeModTOMLData = toml::from_str(str)?;
 
         match raw.mods.clone() {
             None => Ok(raw),
             Some(v) => {
-                let mod_info = v.get(0);
+                let mod_info = v.first();
                 match mod_info {
                     None => Ok(raw),
                     Some(mod_info) => Ok(ForgeModTOMLData {
@@ -159,9 +166,10 @@ impl ForgeModTOMLData {
 impl Parse for ForgeModTOMLData {
     fn parse(self) -> ResolvedMod {
         ResolvedMod {
+            id: self.mod_id.clone(),
             name: match self.display_name {
                 Some(v) => v,
-                None => match self.mod_id {
+                None => match self.mod_id.clone() {
                     Some(v) => v,
                     None => "".to_string(),
                 },
@@ -179,6 +187,7 @@ impl Parse for ForgeModTOMLData {
             version: self.version,
             icon: self.logo_file,
             license: None,
+            file: None,
             depends: {
                 ResolvedDepends {
                     minecraft: None,
@@ -198,7 +207,7 @@ impl ForgeModMcmodInfo {
             .replace("\n", "");
         let info: Vec<ForgeModMcmodInfo> = serde_json::from_str(&file_content)?;
         Ok(info
-            .get(0)
+            .first()
             .ok_or(anyhow::Error::new(std::io::Error::from(
                 std::io::ErrorKind::NotFound,
             )))?
@@ -228,18 +237,19 @@ pub struct ManifestMetadata {
     pub url: Option<String>,
 }
 
-impl ManifestMetadata {
-    pub fn from_str(str: &str) -> Result<ManifestMetadata> {
+impl FromStr for ManifestMetadata {
+    type Err = anyhow::Error;
+
+    fn from_str(str: &str) -> Result<ManifestMetadata> {
         let str = str
             .to_string()
             .lines()
-            .into_iter()
             .map(|line| {
                 let regex = Regex::new(r"\:").unwrap();
                 if regex.is_match(line) {
                     let line = line.replace(": ", ":");
                     let kv = line.split(":").collect::<Vec<&str>>();
-                    let key = kv.get(0).unwrap().replace("-", "").to_string();
+                    let key = kv.first().unwrap().replace("-", "").to_string();
                     let value = kv.get(1).unwrap();
                     format!("{}=\"{}\"", key, value)
                 } else {
@@ -249,20 +259,13 @@ impl ManifestMetadata {
             .collect::<Vec<String>>()
             .join("\n");
         let raw: RawManifest = toml::from_str(&str)?;
-        let mod_id = match raw.tweak_meta_file {
-            Some(v) => Some(v.replace(".json", "")),
-            None => None,
-        };
+        let mod_id = raw.tweak_meta_file.map(|v| v.replace(".json", ""));
         Ok(ManifestMetadata {
-            name: match raw.tweak_name {
-                Some(v) => Some(v),
-                None => None,
-            },
+            name: raw.tweak_name,
             mod_id,
-            authors: match raw.tweak_author {
-                Some(v) => Some(v.split(",").map(|v| v.to_string()).collect()),
-                None => None,
-            },
+            authors: raw
+                .tweak_author
+                .map(|v| v.split(",").map(|v| v.to_string()).collect()),
             description: None,
             url: None,
         })
@@ -272,9 +275,10 @@ impl ManifestMetadata {
 impl Parse for ManifestMetadata {
     fn parse(self) -> ResolvedMod {
         ResolvedMod {
+            id: self.mod_id.clone(),
             name: match self.name {
                 Some(v) => v,
-                None => match self.mod_id {
+                None => match self.mod_id.clone() {
                     Some(v) => v,
                     None => "".to_string(),
                 },
@@ -295,6 +299,7 @@ impl Parse for ManifestMetadata {
             version: None,
             icon: None,
             license: None,
+            file: None,
             depends: {
                 ResolvedDepends {
                     minecraft: None,
@@ -315,9 +320,12 @@ pub struct ResolvedForgeMod {
 }
 
 pub fn parse_mod<P: AsRef<Path>>(path: P) -> Result<ResolvedMod> {
-    let mod_file = File::open(path)?;
+    let mod_file = File::open(&path)?;
     let mut mod_file_archive = ZipArchive::new(mod_file)?;
-    parse_mod_ziparchive(&mut mod_file_archive)
+    Ok(ResolvedMod {
+        file: Some(path.as_ref().to_path_buf()),
+        ..parse_mod_ziparchive(&mut mod_file_archive)?
+    })
 }
 
 pub fn parse_mod_ziparchive(archive: &mut ZipArchive<File>) -> Result<ResolvedMod> {
@@ -326,6 +334,7 @@ pub fn parse_mod_ziparchive(archive: &mut ZipArchive<File>) -> Result<ResolvedMo
         "mcmod.info".to_string(),
         "neimod.info".to_string(),
         "META-INF/mods.toml".to_string(),
+        "META-INF/neoforge.mods.toml".to_string(),
         "META-INF/MANIFEST.MF".to_string(),
     ];
     let entries = filter_entries(archive, &target_entries);
@@ -338,7 +347,10 @@ pub fn parse_mod_ziparchive(archive: &mut ZipArchive<File>) -> Result<ResolvedMo
     } else if let Some(entry) = entries.get("cccmod.info") {
         let file_content = String::from_utf8(entry.content.clone())?;
         ForgeModMcmodInfo::from_info_file(&file_content)?.parse()
-    } else if let Some(entry) = entries.get("META-INF/mods.toml") {
+    } else if let Some(entry) = entries
+        .get("META-INF/neoforge.mods.toml")
+        .or(entries.get("META-INF/mods.toml"))
+    {
         let file_content = String::from_utf8(entry.content.clone())?;
         ForgeModTOMLData::from_str(&file_content)?.parse()
     } else if let Some(entry) = entries.get("META-INF/MANIFEST.MF") {
@@ -16,13 +16,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-//! Mods Parser. It support `forge`, `fabric`, `quilt`, `rift`
+//! Mods Parser. It support `forge`, `neoforge`, `fabric`, `quilt`, `rift`
 //!
 //! If you want to parse mods for a specific mod loader,
 //! you should use `mod_parser::<loader>::parse()` or `mod_parser::<loader>::parse_folder()`,
 //! they filter mods that don't fit the format
 //!
-//! Note: If you want to parse `rift` mods, you should use forge mod parser.
+//! Note: If you want to parse `neoforge` or `rift` mods, you should use forge mod parser.
 //!
 //! # Example
 //!
@@ -47,7 +47,11 @@
 //! println!("{:#?}", result);
 //! ```
 
-use std::{collections::HashMap, ffi::OsStr, path::Path};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Result;
 use serde_json::Value;
@@ -62,6 +66,8 @@ pub trait Parse {
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct ResolvedMod {
+    /// The mod id, like `create` or `fabric-api`.
+    pub id: Option<String>,
     pub name: String,
     pub description: Option<String>,
     pub version: Option<String>,
@@ -69,6 +75,9 @@ pub struct ResolvedMod {
     pub authors: Vec<ResolvedAuthorInfo>,
     pub license: Option<Vec<String>>,
     pub icon: Option<String>,
+
+    /// The mod file, if the mod is parsed from a file.
+    pub file: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
@@ -103,7 +103,7 @@ impl Parse for QuiltModMetadata {
     fn parse(self) -> ResolvedMod {
         let name = match self.name {
             Some(v) => v,
-            None => self.id,
+            None => self.id.clone(),
         };
         let mut minecraft_depend = None;
         let mut loader_depend = None;
@@ -127,7 +127,7 @@ impl Parse for QuiltModMetadata {
                     license
                         .as_array()
                         .unwrap()
-                        .into_iter()
+                        .iter()
                         .map(|v| v.as_str().unwrap().to_string())
                         .collect::<Vec<String>>(),
                 )
@@ -166,6 +166,7 @@ impl Parse for QuiltModMetadata {
             );
         }
         ResolvedMod {
+            id: Some(self.id),
             name,
             description: self.description,
             version: Some(self.version.clone()),
@@ -174,19 +175,20 @@ impl Parse for QuiltModMetadata {
                 mod_loader: loader_depend,
                 java: java_depend,
             },
-            authors: match parsed_authors {
-                Some(v) => v,
-                None => vec![],
-            },
+            authors: parsed_authors.unwrap_or_default(),
             license,
             icon: self.icon,
+            file: None,
         }
     }
 }
 
 pub fn parse_mod<P: AsRef<Path>>(path: P) -> Result<ResolvedMod> {
-    let metadata = QuiltModMetadata::from_path(path)?;
-    Ok(metadata.parse())
+    let metadata = QuiltModMetadata::from_path(&path)?;
+    Ok(ResolvedMod {
+        file: Some(path.as_ref().to_path_buf()),
+        ..metadata.parse()
+    })
 }
 
 pub fn parse_folder<S: AsRef<OsStr> + ?Sized>(folder: &S) -> Result<Vec<ResolvedMod>> {
@@ -203,11 +205,14 @@ pub fn parse_folder<S: AsRef<OsStr> + ?Sized>(folder: &S) -> Result<Vec<Resolved
             continue;
         }
         println!("{:?}", path);
-        let raw_metadata = match QuiltModMetadata::from_path(path) {
+        let raw_metadata = match QuiltModMetadata::from_path(&path) {
             Ok(v) => v,
             Err(_) => continue,
         };
-        result.push(raw_metadata.parse());
+        result.push(ResolvedMod {
+            file: Some(path),
+            ..raw_metadata.parse()
+        });
     }
     Ok(result)
 }
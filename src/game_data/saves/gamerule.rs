@@ -62,7 +62,7 @@ pub struct GameRules {
     /// Whether phantoms can spawn in the nighttime
     pub do_insomnia: Option<String>,
     
-    /// Players respawn immediately without showing the death screen	
+    /// Players respawn immediately without showing the death screen
     pub do_immediate_respawn: Option<String>,
 
     /// Whether players can craft only those recipes that they have unlocked
@@ -87,7 +87,7 @@ pub struct GameRules {
     /// not affected.
     pub do_vines_spread: Option<String>,
     
-    /// Whether the weather can change naturally. The /weather command can still change weather.	
+    /// Whether the weather can change naturally. The /weather command can still change weather.
     pub do_weather_cycle: Option<String>,
 
     /// Whether wardens can spawn
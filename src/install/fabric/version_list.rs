@@ -19,11 +19,12 @@
 use anyhow::Result;
 
 use super::*;
+use crate::core::source;
 
 impl FabricArtifacts {
     /// get fabric artifacts
     pub async fn new() -> Result<Self> {
-        Ok(reqwest::get("https://meta.fabricmc.net/v2/versions")
+        Ok(source::get("https://meta.fabricmc.net/v2/versions")
             .await?
             .json()
             .await?)
@@ -33,14 +34,14 @@ impl FabricArtifacts {
 impl YarnArtifactList {
     /// get yarn artifacts
     pub async fn new() -> Result<Self> {
-        Ok(reqwest::get("https://meta.fabricmc.net/v2/versions/yarn")
+        Ok(source::get("https://meta.fabricmc.net/v2/versions/yarn")
             .await?
             .json()
             .await?)
     }
     /// get the yarn of the specified minecraft version
     pub async fn from_mcversion(mcversion: &str) -> Result<Self> {
-        Ok(reqwest::get(format!(
+        Ok(source::get(&format!(
             "https://meta.fabricmc.net/v2/versions/yarn/{}",
             mcversion
         ))
@@ -53,14 +54,14 @@ impl YarnArtifactList {
 impl LoaderArtifactList {
     /// get loader artifacts
     pub async fn new() -> Result<Self> {
-        Ok(reqwest::get("https://meta.fabricmc.net/v2/versions/loader")
+        Ok(source::get("https://meta.fabricmc.net/v2/versions/loader")
             .await?
             .json()
             .await?)
     }
     /// get the loader of the specified minecraft version
     pub async fn from_mcversion(mcversion: &str) -> Result<Self> {
-        Ok(reqwest::get(format!(
+        Ok(source::get(&format!(
             "https://meta.fabricmc.net/v2/versions/loader/{}",
             mcversion
         ))
@@ -73,7 +74,7 @@ impl LoaderArtifactList {
 impl FabricLoaderArtifact {
     /// get fabric loader artifact
     pub async fn new(mcversion: &str, loader: &str) -> Result<Self> {
-        Ok(reqwest::get(format!(
+        Ok(source::get(&format!(
             "https://meta.fabricmc.net/v2/versions/loader/{}/{}",
             mcversion, loader
         ))
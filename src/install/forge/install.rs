@@ -21,93 +21,69 @@ use std::{
     io::{self, Read},
     path::{Path, PathBuf},
     str::FromStr,
-    thread,
-    time::Duration,
 };
 
 use anyhow::Result;
-use regex::Regex;
-use reqwest::Response;
-use tokio::io::AsyncWriteExt;
 use zip::ZipArchive;
 
 use crate::{
     core::{
         folder::MinecraftLocation,
-        version::{LibraryDownload, MinecraftVersion},
-        HTTP_CLIENT,
+        version::{LibraryInfo, MinecraftVersion},
+        Download,
     },
     install::forge::{
         install_profile::{InstallProfile, InstallProfileLegacy},
         legacy_install::install_legacy_forge_from_zip,
         new_install::unpack_forge_installer,
+        processor::{download_profile_libraries, run_processors},
     },
-    utils::unzip::filter_entries,
+    utils::{download::download, unzip::filter_entries},
 };
 
 use super::*;
 
-// const DEFAULT_FORGE_MAVEN: &str = "https://files.minecraftforge.net/maven";
-
 async fn download_forge_installer(
-    required_version: RequiredVersion,
+    required_version: &RequiredVersion,
+    forge_version: &str,
     minecraft: &MinecraftLocation,
     _options: &Option<InstallForgeOptions>,
-) -> Result<(String, Response)> {
-    let url = find_download_link(&required_version.version, &required_version.mcversion).await?;
-    let path = url.replace("https://maven.minecraftforge.net/", "");
-    // let forge_maven_path = path.replace("/maven", "").replace("maven", "");
-    let sha1 = match &required_version.installer {
-        Some(installer) => match &installer.sha1 {
-            Some(sha1) => Some(String::from(sha1)),
-            None => None,
-        },
-        None => None,
+) -> Result<PathBuf> {
+    let (path, sha1) = match &required_version.installer {
+        Some(installer) => (
+            installer.path.trim_start_matches('/').to_string(),
+            installer.sha1.clone(),
+        ),
+        None => (
+            forge_installer_path(&required_version.mcversion, forge_version),
+            None,
+        ),
     };
-    let library = LibraryDownload {
-        url,
-        path,
-        size: None,
+    let file_path = minecraft.get_library_by_path(&path);
+    download(Download {
+        url: format!("{DEFAULT_FORGE_MAVEN}/{path}"),
+        file: file_path.clone(),
         sha1,
-    };
-    let file_path = minecraft
-        .get_library_by_path(&library.path)
-        .to_str()
-        .ok_or(std::io::Error::from(std::io::ErrorKind::NotFound))
-        .unwrap()
-        .to_string();
-    // let response = download(Download {
-    //     url: library.url,
-    //     file: file_path.clone(),
-    //     sha1: None,
-    // })
-    // .await;
-    let mut response = HTTP_CLIENT.get(library.url).send().await?;
-    let mut file = tokio::fs::File::create(&file_path).await?;
-    while let Some(chunk) = response.chunk().await.unwrap() {
-        file.write_all(&chunk).await.unwrap();
-    }
-    Ok((file_path, response))
+    })
+    .await?;
+    Ok(file_path)
 }
 
 async fn walk_forge_installer_entries<R: Read + io::Seek>(
     mut zip: ZipArchive<R>,
+    artifact: &str,
     forge_version: &str,
 ) -> ForgeInstallerEntries {
+    let base = LibraryInfo::from_name(&format!("{artifact}:{forge_version}")).path;
+    let base = base.trim_end_matches(".jar");
     let entries = vec![
-        format!(
-            "maven/net/minecraftforge/forge/{}/forge-{}.jar",
-            forge_version, forge_version
-        ),
-        format!(
-            "maven/net/minecraftforge/forge/{}/forge-{}-universal.jar",
-            forge_version, forge_version
-        ),
+        format!("maven/{base}.jar"),
+        format!("maven/{base}-universal.jar"),
         "data/client.lzma".to_string(),
         "data/server.lzma".to_string(),
         "install_profile.json".to_string(),
         "version.json".to_string(),
-        format!("forge-{}-universal.jar", forge_version),
+        format!("forge-{forge_version}-universal.jar"),
         "data/run.sh".to_string(),
         "data/run.bat".to_string(),
         "data/unix_args.txt".to_string(),
@@ -116,10 +92,9 @@ async fn walk_forge_installer_entries<R: Read + io::Seek>(
     ];
     let filted_entries = filter_entries(&mut zip, &entries);
     let get_content = move |index: usize| -> Option<Entry> {
-        match filted_entries.get(entries.clone().get(index).unwrap()) {
-            None => None,
-            Some(value) => Some(value.clone()),
-        }
+        filted_entries
+            .get(entries.clone().get(index).unwrap())
+            .cloned()
     };
     ForgeInstallerEntries {
         forge_jar: get_content(0),
@@ -142,192 +117,174 @@ pub async fn install_forge(
     minecraft: MinecraftLocation,
     options: Option<InstallForgeOptions>,
 ) -> Result<()> {
-    let mcversion: Vec<_> = version.mcversion.split(".").collect();
-    let minor = *mcversion.get(1).unwrap();
-    let minor_version = minor.parse::<u8>().unwrap();
-    let patch = match mcversion.get(2) {
-        Some(patch) => Some(*patch),
-        None => None,
-    };
-    let forge_version = get_forge_version(minor_version, patch, &version);
-
-    let (installer_jar_path, _installer_jar) =
-        download_forge_installer(version, &minecraft, &options)
-            .await
-            .unwrap();
-    println!("{}", installer_jar_path);
+    let forge_version = get_forge_version(&version)?;
 
-    let file = Path::new(&installer_jar_path);
-    if file.exists() {
-        let file_size = std::fs::metadata(file)?.len();
-        if file_size == 0 {
-            eprintln!("ZIP file is empty");
-            return Err(anyhow::anyhow!(""));
-        }
-    } else {
-        eprintln!("ZIP file does not exist");
-        return Err(anyhow::anyhow!(""));
-    }
+    let installer_jar_path =
+        download_forge_installer(&version, &forge_version, &minecraft, &options).await?;
+    install_from_installer(
+        &installer_jar_path,
+        FORGE_ARTIFACT,
+        &forge_version,
+        minecraft,
+        options,
+    )
+    .await
+}
 
-    thread::sleep(Duration::from_secs(1));
-    let installer_jar = ZipArchive::new(File::open(&installer_jar_path).unwrap()).unwrap();
+/// Install from a downloaded installer jar of forge or neoforge.
+///
+/// `artifact` is the maven `group:artifact` of the loader, and `forge_version` is its maven
+/// version, they locate the files of the loader in the installer.
+pub(crate) async fn install_from_installer(
+    file: &Path,
+    artifact: &str,
+    forge_version: &str,
+    minecraft: MinecraftLocation,
+    options: Option<InstallForgeOptions>,
+) -> Result<()> {
+    let open_installer = || -> Result<ZipArchive<File>> {
+        let reader = File::open(file).map_err(|err| {
+            anyhow::anyhow!("Cannot open forge installer {}: {err}", file.display())
+        })?;
+        ZipArchive::new(reader)
+            .map_err(|err| anyhow::anyhow!("Bad forge installer jar {}: {err}", file.display()))
+    };
 
-    let entries = walk_forge_installer_entries(installer_jar, &forge_version).await;
-    let mut installer_jar = ZipArchive::new(File::open(&installer_jar_path).unwrap()).unwrap();
+    let entries = walk_forge_installer_entries(open_installer()?, artifact, forge_version).await;
+    let mut installer_jar = open_installer()?;
 
     let install_profile_json = match &entries.install_profile_json {
-        None => return Err(anyhow::anyhow!("Bad forge installer jar!")),
-        Some(data) => String::from_utf8(data.content.clone()).unwrap(),
-    };
-    println!("{}", install_profile_json);
-    let forge_type = if let Some(_) = &entries.install_profile_json {
-        if let Some(_) = entries.version_json {
-            ForgeType::New
-        } else if let Some(_) = &entries.legacy_universal_jar {
-            ForgeType::Legacy
-        } else {
-            ForgeType::Bad
+        None => {
+            return Err(anyhow::anyhow!(
+                "No install profile in forge installer jar {}",
+                file.display()
+            ))
         }
+        Some(data) => String::from_utf8(data.content.clone()).map_err(|_| {
+            anyhow::anyhow!(
+                "Install profile of forge installer jar {} is not UTF-8",
+                file.display()
+            )
+        })?,
+    };
+    let forge_type = if entries.version_json.is_some() {
+        ForgeType::New
+    } else if entries.legacy_universal_jar.is_some() {
+        ForgeType::Legacy
     } else {
         ForgeType::Bad
     };
     match forge_type {
         ForgeType::New => {
-            let profile: InstallProfile = serde_json::from_str(&install_profile_json).unwrap();
-            let _version_id = unpack_forge_installer(
+            let mut profile: InstallProfile = serde_json::from_str(&install_profile_json)?;
+            let java = options
+                .as_ref()
+                .and_then(|options| options.java.clone())
+                .unwrap_or("java".to_string());
+            let installer_path = file.to_path_buf();
+            unpack_forge_installer(
                 &mut installer_jar,
                 entries,
-                &forge_version,
-                minecraft,
-                PathBuf::from_str((&installer_jar_path).as_ref()).unwrap(),
-                profile,
+                &format!("{artifact}:{forge_version}"),
+                minecraft.clone(),
+                installer_path.clone(),
+                &mut profile,
                 options,
             )
-            .await;
+            .await?;
+            download_profile_libraries(&profile, &minecraft).await?;
+            run_processors(&profile, &installer_path, &minecraft, &java).await?;
         }
         ForgeType::Legacy => {
-            let profile: InstallProfileLegacy =
-                serde_json::from_str(&install_profile_json).unwrap();
+            let profile: InstallProfileLegacy = serde_json::from_str(&install_profile_json)?;
+            let (Some(install_profile_json), Some(legacy_universal_jar)) =
+                (entries.install_profile_json, entries.legacy_universal_jar)
+            else {
+                return Err(anyhow::anyhow!(
+                    "Bad forge installer jar {}",
+                    file.display()
+                ));
+            };
             let entries = ForgeLegacyInstallerEntriesPatten {
-                install_profile_json: entries
-                    .install_profile_json
-                    .ok_or(std::io::Error::from(std::io::ErrorKind::NotFound))
-                    .unwrap(),
-                legacy_universal_jar: entries
-                    .legacy_universal_jar
-                    .ok_or(std::io::Error::from(std::io::ErrorKind::NotFound))
-                    .unwrap(),
+                install_profile_json,
+                legacy_universal_jar,
             };
-            install_legacy_forge_from_zip(entries, profile, minecraft, options)
-                .await
-                .unwrap();
+            install_legacy_forge_from_zip(entries, profile, minecraft, options).await?;
+        }
+        ForgeType::Bad => {
+            return Err(anyhow::anyhow!(
+                "Bad forge installer jar {}",
+                file.display()
+            ))
         }
-        ForgeType::Bad => return Err(anyhow::anyhow!("Bad forge installer jar!")),
     }
 
     Ok(())
 }
 
-fn get_forge_version(minor_version: u8, patch: Option<&str>, version: &RequiredVersion) -> String {
-    if (minor_version >= 7 && minor_version <= 9) || (minor_version == 10 && patch.is_none()) {
-        match patch {
-            Some(patch) => {
-                if (&version.version == "10.12.2.1154"
-                    || &version.version == "10.12.2.1155"
-                    || &version.version == "10.12.2.1161")
-                    && patch == "2"
-                {
-                    format!(
-                        "{}-{}-mc{}",
-                        version.mcversion,
-                        version.version,
-                        version.mcversion.replace(".", "")
-                    )
-                } else {
-                    format!(
-                        "{mc}-{forge}-{mc}",
-                        mc = version.mcversion,
-                        forge = version.version
-                    )
-                }
+/// The maven version of forge, like `1.20.1-47.1.0` or `1.7.10-10.13.4.1614-1.7.10`.
+fn get_forge_version(version: &RequiredVersion) -> Result<String> {
+    // the installer is in the folder of the maven version
+    let from_installer = version.installer.as_ref().and_then(|installer| {
+        installer
+            .path
+            .rsplit('/')
+            .nth(1)
+            .filter(|folder| folder.starts_with(&format!("{}-", version.mcversion)))
+    });
+    if let Some(forge_version) = from_installer {
+        return Ok(forge_version.to_string());
+    }
+    let mcversion = MinecraftVersion::from_str(&version.mcversion)?;
+    // the forge versions from 1.7 to 1.10 have the minecraft version as suffix
+    let legacy = mcversion >= MinecraftVersion::Release(1, 7, None)
+        && mcversion <= MinecraftVersion::Release(1, 10, None);
+    Ok(match mcversion {
+        MinecraftVersion::Release(_, _, Some(patch)) if legacy => {
+            if (&version.version == "10.12.2.1154"
+                || &version.version == "10.12.2.1155"
+                || &version.version == "10.12.2.1161")
+                && patch == 2
+            {
+                format!(
+                    "{}-{}-mc{}",
+                    version.mcversion,
+                    version.version,
+                    version.mcversion.replace(".", "")
+                )
+            } else {
+                format!(
+                    "{mc}-{forge}-{mc}",
+                    mc = version.mcversion,
+                    forge = version.version
+                )
             }
-            None => format!(
-                "{mc}-{forge}-{mc}.0",
-                mc = version.mcversion,
-                forge = version.version
-            ),
         }
-    } else {
-        format!("{}-{}", version.mcversion, version.version)
-    }
+        MinecraftVersion::Release(_, _, None) if legacy => format!(
+            "{mc}-{forge}-{mc}.0",
+            mc = version.mcversion,
+            forge = version.version
+        ),
+        _ => format!("{}-{}", version.mcversion, version.version),
+    })
 }
 
-/// Find installer download link from forge website.
-pub async fn find_download_link(forge_version: &str, minecraft_version: &str) -> Result<String> {
-    let document_url = format!(
-        "https://files.minecraftforge.net/net/minecraftforge/forge/index_{minecraft_version}.html"
-    );
-    let document = HTTP_CLIENT
-        .get(document_url)
-        .send()
-        .await?
-        .text()
-        .await?
-        .replace("\r\n", "\n");
-    let (minor_version, revised_version) = if let MinecraftVersion::Release(_, minor, revised) =
-        MinecraftVersion::from_str(minecraft_version).unwrap()
-    {
-        (minor, revised)
-    } else {
-        return Err(anyhow::anyhow!("Not a valid minecraft version"));
+/// The path of the installer in the forge maven, `forge_version` is the maven version.
+///
+/// Forge before 1.5.2 has no installer, the universal zip, or the client zip before 1.3, is
+/// returned instead.
+pub fn forge_installer_path(mcversion: &str, forge_version: &str) -> String {
+    let (classifier, extension) = match MinecraftVersion::from_str(mcversion) {
+        Ok(version) if version < MinecraftVersion::Release(1, 3, None) => ("client", "zip"),
+        Ok(version) if version < MinecraftVersion::Release(1, 5, Some(2)) => ("universal", "zip"),
+        _ => ("installer", "jar"),
     };
-    let document_split = document
-        .split("\n")
-        .filter(|x| {
-            let installer = if minor_version < 3 {
-                "clie"
-            } else if minor_version <= 5 && revised_version.is_some() {
-                if revised_version.unwrap() == 2 {
-                    "ins"
-                } else {
-                    "uni"
-                }
-            } else {
-                "ins"
-            };
-            x.contains("href=")
-                && (x.contains(installer))
-                && x.contains(&format!("{forge_version}-"))
-                && !x.contains("adfoc.us")
-                && !x.contains("mdk")
-                && !x.contains("changelog.txt")
-                && !x.contains("info")
-        })
-        .collect::<Vec<&str>>();
-    // println!("{document_split:?}");
-    if document_split.len() != 1 {
-        return Err(anyhow::anyhow!("cannot find download link"));
-    }
-    let link_element = document_split
-        .get(0)
-        .ok_or(anyhow::anyhow!("cannot find download link"))?;
-
-    let regex = Regex::new(r#"href="(.*?)""#)?;
-    let link = regex
-        .captures(link_element)
-        .ok_or(anyhow::anyhow!("cannot find download link"))?
-        .get(1)
-        .ok_or(anyhow::anyhow!("cannot find download link"))?
-        .as_str();
-    Ok(link.to_string())
+    LibraryInfo::from_name(&format!(
+        "{FORGE_ARTIFACT}:{forge_version}:{classifier}@{extension}"
+    ))
+    .path
 }
 
-// #[tokio::test]
-// async fn test() {
-//     let a = find_download_link("44.0.1", "1.19.3");
-//     println!("{}", a.await.unwrap());
-// }
-
 // #[tokio::test]
 // async fn test() {
 // use crate::install::TaskEventListeners;
@@ -340,8 +297,6 @@ pub async fn find_download_link(forge_version: &str, minecraft_version: &str) ->
 // )
 // .await
 // .unwrap();
-// let a = find_download_link("25.0.160", "1.13.2").await.unwrap();
-// println!("{a}");
 // install_forge(
 //     RequiredVersion {
 //         installer: None,
@@ -370,3 +325,43 @@ pub async fn find_download_link(forge_version: &str, minecraft_version: &str) ->
 //     .await
 //     .unwrap();
 // }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn maven_versions() {
+        let required = |mcversion: &str, version: &str| RequiredVersion {
+            installer: None,
+            mcversion: mcversion.to_string(),
+            version: version.to_string(),
+        };
+        assert_eq!(
+            get_forge_version(&required("1.20.1", "47.1.0")).unwrap(),
+            "1.20.1-47.1.0"
+        );
+        assert_eq!(
+            get_forge_version(&required("1.7.10", "10.13.4.1614")).unwrap(),
+            "1.7.10-10.13.4.1614-1.7.10"
+        );
+        assert_eq!(
+            get_forge_version(&required("1.7.2", "10.12.2.1161")).unwrap(),
+            "1.7.2-10.12.2.1161-mc172"
+        );
+        assert_eq!(
+            forge_installer_path("1.7.10", "1.7.10-10.13.4.1614-1.7.10"),
+            "net/minecraftforge/forge/1.7.10-10.13.4.1614-1.7.10/forge-1.7.10-10.13.4.1614-1.7.10-installer.jar"
+        );
+
+        // the maven version in the installer path wins
+        let version = RequiredVersion {
+            installer: Some(RequiredVersionInstaller {
+                sha1: None,
+                path: forge_installer_path("1.10", "1.10-12.18.0.2000"),
+            }),
+            ..required("1.10", "12.18.0.2000")
+        };
+        assert_eq!(get_forge_version(&version).unwrap(), "1.10-12.18.0.2000");
+    }
+}
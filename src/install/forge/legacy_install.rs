@@ -55,14 +55,9 @@ pub(super) async fn install_legacy_forge_from_zip(
     let library = version_json.libraries.clone().unwrap();
     let library = library
         .iter()
-        .find(|l| {
-            l["name"]
-                .as_str()
-                .unwrap()
-                .starts_with("net.minecraftforge:forge")
-        })
+        .find(|l| l.name.starts_with("net.minecraftforge:forge"))
         .unwrap();
-    let library = LibraryInfo::from_value(library);
+    let library = LibraryInfo::from_name(&library.name);
 
     fs::write(
         version_json_path,
@@ -24,8 +24,19 @@ pub mod install;
 pub mod install_profile;
 pub mod legacy_install;
 pub mod new_install;
+pub mod processor;
 pub mod version_list;
 
+/// The maven `group:artifact` of forge.
+pub(crate) const FORGE_ARTIFACT: &str = "net.minecraftforge:forge";
+
+const DEFAULT_FORGE_MAVEN: &str = "https://maven.minecraftforge.net";
+
+/// The recommended and the latest forge of each Minecraft version.
+const PROMOTIONS_URL: &str =
+    "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct ForgeVersion {
     pub installer: ForgeVersionInstaller,
     pub universal: ForgeVersionUniversal,
@@ -38,27 +49,31 @@ pub struct ForgeVersion {
     pub r#type: ForgeVersionType,
 }
 
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct ForgeVersionInstaller {
-    pub md5: String,
-    pub sha1: String,
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
 
     /// The url path to concat with forge maven
     pub path: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct ForgeVersionUniversal {
-    pub md5: String,
-    pub sha1: String,
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
 
     /// The url path to concat with forge maven
     pub path: String,
 }
 
+/// The marker of the version in the promotions of forge
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub enum ForgeVersionType {
-    Buggy(String),
-    Recommended(String),
-    Common(String),
-    Latest(String),
+    Buggy,
+    Recommended,
+    Common,
+    Latest,
 }
 
 /// All the useful entries in forge installer jar
@@ -144,6 +159,19 @@ pub struct ForgeLegacyInstallerEntriesPatten {
     pub legacy_universal_jar: Entry,
 }
 
+impl From<ForgeVersion> for RequiredVersion {
+    fn from(version: ForgeVersion) -> Self {
+        Self {
+            installer: Some(RequiredVersionInstaller {
+                sha1: version.installer.sha1,
+                path: version.installer.path,
+            }),
+            mcversion: version.mcversion,
+            version: version.version,
+        }
+    }
+}
+
 pub struct RequiredVersion {
     pub installer: Option<RequiredVersionInstaller>,
     pub mcversion: String,
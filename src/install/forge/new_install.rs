@@ -28,7 +28,10 @@ use tokio::fs::{self, create_dir_all};
 use zip::ZipArchive;
 
 use crate::{
-    core::{folder::MinecraftLocation, version::Version},
+    core::{
+        folder::MinecraftLocation,
+        version::{LibraryInfo, Version},
+    },
     utils::unzip::decompression_files,
 };
 
@@ -37,18 +40,19 @@ use super::{
     *,
 };
 
+/// Unpack the installer of the loader `loader`, the maven name `group:artifact:version`.
 pub(super) async fn unpack_forge_installer<R: Read + io::Seek>(
     zip: &mut ZipArchive<R>,
     entries: ForgeInstallerEntries,
-    forge_version: &String,
+    loader: &str,
     minecraft: MinecraftLocation,
     jar_path: PathBuf,
-    profile: InstallProfile,
+    profile: &mut InstallProfile,
     options: Option<InstallForgeOptions>,
 ) -> Result<String> {
     let version_json_raw = entries.version_json.unwrap().content;
     let mut version_json: Value =
-        serde_json::from_str((&String::from_utf8(version_json_raw)?).as_ref())?;
+        serde_json::from_str(String::from_utf8(version_json_raw)?.as_ref())?;
 
     //  apply override for inheritsFrom
     if let Some(options) = options {
@@ -73,18 +77,18 @@ pub(super) async fn unpack_forge_installer<R: Read + io::Seek>(
 
     create_dir_all(version_json_path.parent().unwrap()).await?;
 
-    if let Some(_) = entries.forge_universal_jar {
-        decompression_tasks.push((
-            format!(
-                "maven/net/minecraftforge/forge/{}/forge-{}-universal.jar",
-                forge_version, forge_version
-            ),
-            minecraft.libraries.clone().join(format!(
-                "maven/net/minecraftforge/forge/{}/forge-{}-universal.jar",
-                forge_version, forge_version
-            )),
-        ));
-    }
+    // the installer ships the jars of the loader, like the universal jar, in a maven layout
+    let shipped_libraries = zip
+        .file_names()
+        .filter(|name| name.starts_with("maven/") && !name.ends_with('/'))
+        .map(|name| {
+            (
+                name.to_string(),
+                minecraft.get_library_by_path(&name["maven/".len()..]),
+            )
+        })
+        .collect::<Vec<_>>();
+    decompression_tasks.extend(shipped_libraries);
     let mut profile_data;
     if let Some(h) = profile.data.clone() {
         profile_data = h;
@@ -92,17 +96,16 @@ pub(super) async fn unpack_forge_installer<R: Read + io::Seek>(
         profile_data = HashMap::new();
     }
 
-    let installer_maven = format!("net.minecraftforge:forge:{forge_version}:installer");
+    let installer_maven = format!("{loader}:installer");
     let profile_data_installer = InstallProfileData {
         client: Some(format!("[{installer_maven}]")),
         server: Some(format!("[{installer_maven}]")),
     };
     profile_data.insert("INSTALLER".to_string(), profile_data_installer);
 
-    let path = &format!("net/minecraftforge/forge/{forge_version}/forge-{forge_version}.jar");
     if let Some(server_lzma) = entries.server_lzma {
         // forge version and mavens, compatible with twitch api
-        let server_maven = format!("net.minecraftforge:forge:{forge_version}:serverdata@lzma");
+        let server_maven = format!("{loader}:serverdata@lzma");
         // override forge bin patch location
         profile_data.insert(
             "BINPATCH".to_string(),
@@ -112,13 +115,14 @@ pub(super) async fn unpack_forge_installer<R: Read + io::Seek>(
             },
         );
 
-        let server_bin_path = minecraft.libraries.join(path);
+        let server_bin_path =
+            minecraft.get_library_by_path(LibraryInfo::from_name(&server_maven).path);
         decompression_tasks.push((server_lzma.name.clone(), server_bin_path));
     }
 
     if let Some(client_lzma) = entries.client_lzma {
         //forge version and mavens, compatible with twitch api
-        let client_maven = format!("net.minecraftforge:forge:{forge_version}:clientdata@lzma");
+        let client_maven = format!("{loader}:clientdata@lzma");
         //override forge bin patch location
         let mut server = String::new();
         let binpatch = profile_data.get("BINPATCH");
@@ -130,29 +134,16 @@ pub(super) async fn unpack_forge_installer<R: Read + io::Seek>(
         profile_data.insert(
             "BINPATCH".to_string(),
             InstallProfileData {
-                client: Some(format!("[{client_maven}]]")),
+                client: Some(format!("[{client_maven}]")),
                 server: Some(server),
             },
         );
 
-        let client_bin_path = minecraft.libraries.join(format!(
-            "net/minecraftforge/forge/{forge_version}/forge-{forge_version}.jar"
-        ));
+        let client_bin_path =
+            minecraft.get_library_by_path(LibraryInfo::from_name(&client_maven).path);
         decompression_tasks.push((client_lzma.name.clone(), client_bin_path));
     }
 
-    if let Some(forge_jar) = entries.forge_jar {
-        let file_name = match entries.forge_universal_jar {
-            Some(v) => v.name,
-            None => forge_jar.name,
-        };
-        fs::write(
-            minecraft.get_library_by_path(&file_name[file_name.find("/").unwrap() + 1..]),
-            forge_jar.content,
-        )
-        .await?;
-    }
-
     let unpack_data = |entry: Entry| -> Result<()> {
         let path = data_root.clone().join(entry.name);
         std::fs::create_dir_all(path.parent().unwrap())?;
@@ -176,8 +167,9 @@ pub(super) async fn unpack_forge_installer<R: Read + io::Seek>(
         unpack_data(unix_jvm_args)?;
     }
 
+    profile.data = Some(profile_data);
     create_dir_all(install_json_path.parent().unwrap()).await?;
-    fs::write(install_json_path, serde_json::to_string_pretty(&profile)?).await?;
+    fs::write(install_json_path, serde_json::to_string_pretty(profile)?).await?;
 
     create_dir_all(version_json_path.parent().unwrap()).await?;
     fs::write(
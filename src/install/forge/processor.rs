@@ -0,0 +1,434 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The post processors of the forge installer
+//!
+//! Since 1.13, the forge installer doesn't ship the patched client. The `processors` of
+//! install_profile.json (jarsplitter, binarypatcher, SpecialSource, installertools...) build it
+//! from the vanilla jar, so they must run before the version can launch.
+//!
+//! The arguments of a processor may contain `{KEY}`, which is replaced by the value of `KEY` in
+//! the `data` of the profile, and `[group:artifact:version]`, which is replaced by the path of
+//! the artifact in the libraries folder. A data value is also either a maven name in `[]`, a
+//! literal in `''`, or a file in the installer jar starting with `/`.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use regex::{Captures, Regex};
+use tokio::process::Command;
+use zip::ZipArchive;
+
+use crate::{
+    core::{
+        folder::MinecraftLocation,
+        version::{Library, LibraryInfo},
+        Download, DELIMITER,
+    },
+    utils::download::{check_file_sha1, file_sha1, DownloadProgress, Downloader},
+};
+
+use super::install_profile::{InstallProfile, PostProcessor};
+
+/// Only the client is installed, the processors for the server are skipped.
+const SIDE: &str = "client";
+
+/// Download the libraries the processors need.
+///
+/// The libraries without url are shipped in the installer jar, they are unpacked before.
+pub async fn download_profile_libraries(
+    profile: &InstallProfile,
+    minecraft: &MinecraftLocation,
+) -> Result<DownloadProgress> {
+    let libraries: Vec<Library> = serde_json::from_value(profile.libraries.clone())?;
+    let downloads = libraries
+        .into_iter()
+        .filter_map(|library| {
+            let artifact = library.downloads?.artifact?;
+            if artifact.url.is_empty() {
+                return None;
+            }
+            let path = match artifact.path.as_str() {
+                "" => LibraryInfo::from_name(&library.name).path,
+                path => path.to_string(),
+            };
+            Some(Download {
+                url: artifact.url,
+                file: minecraft.get_library_by_path(path),
+                sha1: artifact.sha1,
+            })
+        })
+        .collect();
+    Downloader::default()
+        .download_all(downloads)
+        .await
+        .into_result()
+}
+
+/// Run the client processors of the profile one by one with `java`.
+///
+/// A processor is skipped if all its outputs already exist with the expected sha1, and its
+/// outputs are checked after it runs.
+pub async fn run_processors(
+    profile: &InstallProfile,
+    installer: &Path,
+    minecraft: &MinecraftLocation,
+    java: &str,
+) -> Result<()> {
+    let processors: Vec<&PostProcessor> = profile
+        .processors
+        .iter()
+        .flatten()
+        .filter(|processor| {
+            processor
+                .sides
+                .as_ref()
+                .is_none_or(|sides| sides.iter().any(|side| side == SIDE))
+        })
+        .collect();
+    if processors.is_empty() {
+        return Ok(());
+    }
+
+    // the files of the installer jar referred by data are extracted here
+    let extract_root =
+        std::env::temp_dir().join(format!("forge-installer-{}", uuid::Uuid::new_v4()));
+    let result = async {
+        let data = resolve_data(profile, installer, &extract_root, minecraft)?;
+        for processor in processors {
+            run_processor(processor, &data, minecraft, java).await?;
+        }
+        Ok(())
+    }
+    .await;
+    if extract_root.exists() {
+        tokio::fs::remove_dir_all(&extract_root).await?;
+    }
+    result
+}
+
+/// Build the variables for the arguments: the `data` of the profile for the client, and the
+/// variables provided by the installer itself.
+fn resolve_data(
+    profile: &InstallProfile,
+    installer: &Path,
+    extract_root: &Path,
+    minecraft: &MinecraftLocation,
+) -> Result<HashMap<String, String>> {
+    let mut data = HashMap::new();
+    let mut installer_jar = None;
+    for (key, value) in profile.data.iter().flatten() {
+        let value = match &value.client {
+            Some(value) => value,
+            None => continue,
+        };
+        let resolved = if let Some(name) = maven_name(value) {
+            library_path(minecraft, name)
+        } else if let Some(literal) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+            literal.to_string()
+        } else if let Some(entry) = value.strip_prefix('/') {
+            let zip = match &mut installer_jar {
+                Some(zip) => zip,
+                None => installer_jar.insert(ZipArchive::new(File::open(installer)?)?),
+            };
+            let mut buf = Vec::new();
+            zip.by_name(entry)?.read_to_end(&mut buf)?;
+            let path = extract_root.join(entry);
+            std::fs::create_dir_all(
+                path.parent()
+                    .ok_or(std::io::Error::from(std::io::ErrorKind::NotFound))?,
+            )?;
+            std::fs::write(&path, buf)?;
+            path.to_string_lossy().to_string()
+        } else {
+            value.clone()
+        };
+        data.insert(key.clone(), resolved);
+    }
+
+    let minecraft_jar = minecraft.get_version_jar(&profile.minecraft, None);
+    if !minecraft_jar.is_file() {
+        return Err(anyhow!(
+            "Minecraft {} should be installed before forge, {} is missing",
+            profile.minecraft,
+            minecraft_jar.display()
+        ));
+    }
+    let builtin = [
+        ("SIDE", SIDE.to_string()),
+        ("MINECRAFT_JAR", minecraft_jar.to_string_lossy().to_string()),
+        ("MINECRAFT_VERSION", profile.minecraft.clone()),
+        ("ROOT", minecraft.root.to_string_lossy().to_string()),
+        ("INSTALLER", installer.to_string_lossy().to_string()),
+        (
+            "LIBRARY_DIR",
+            minecraft.libraries.to_string_lossy().to_string(),
+        ),
+    ];
+    for (key, value) in builtin {
+        data.insert(key.to_string(), value);
+    }
+    Ok(data)
+}
+
+async fn run_processor(
+    processor: &PostProcessor,
+    data: &HashMap<String, String>,
+    minecraft: &MinecraftLocation,
+    java: &str,
+) -> Result<()> {
+    let outputs = resolve_outputs(processor, data, minecraft)?;
+    if !outputs.is_empty() && outputs_match(&outputs).await {
+        return Ok(());
+    }
+
+    let jar = PathBuf::from(library_path(minecraft, &processor.jar));
+    let main_class = main_class(&jar)?;
+    let mut classpath = vec![jar.to_string_lossy().to_string()];
+    classpath.extend(
+        processor
+            .classpath
+            .iter()
+            .map(|name| library_path(minecraft, name)),
+    );
+    let args = processor
+        .args
+        .iter()
+        .map(|arg| resolve_arg(arg, data, minecraft))
+        .collect::<Result<Vec<_>>>()?;
+
+    let output = Command::new(java)
+        .arg("-cp")
+        .arg(classpath.join(DELIMITER))
+        .arg(&main_class)
+        .args(&args)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Processor {} failed with {}: {}",
+            processor.jar,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    for (file, sha1) in &outputs {
+        let actual = file_sha1(file).await.unwrap_or_default();
+        if !actual.eq_ignore_ascii_case(sha1) {
+            let _ = tokio::fs::remove_file(file).await;
+            return Err(anyhow!(
+                "Processor {} made a bad output {}, expected sha1 {sha1} but got {actual}",
+                processor.jar,
+                file.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The output files of the processor and their sha1.
+fn resolve_outputs(
+    processor: &PostProcessor,
+    data: &HashMap<String, String>,
+    minecraft: &MinecraftLocation,
+) -> Result<Vec<(PathBuf, String)>> {
+    processor
+        .outputs
+        .iter()
+        .flatten()
+        .map(|(file, sha1)| {
+            let file = resolve_arg(file, data, minecraft)?;
+            let sha1 = resolve_arg(sha1, data, minecraft)?;
+            let sha1 = sha1.trim_matches('\'').to_string();
+            Ok((PathBuf::from(file), sha1))
+        })
+        .collect()
+}
+
+async fn outputs_match(outputs: &[(PathBuf, String)]) -> bool {
+    for (file, sha1) in outputs {
+        if !check_file_sha1(file, sha1).await {
+            return false;
+        }
+    }
+    true
+}
+
+/// Replace the `[maven]` argument and the `{KEY}` in the argument.
+fn resolve_arg(
+    arg: &str,
+    data: &HashMap<String, String>,
+    minecraft: &MinecraftLocation,
+) -> Result<String> {
+    if let Some(name) = maven_name(arg) {
+        return Ok(library_path(minecraft, name));
+    }
+    let regex = Regex::new(r"\{(\w+)\}")?;
+    let mut missing = None;
+    let resolved = regex.replace_all(arg, |captures: &Captures| {
+        let key = &captures[1];
+        match data.get(key) {
+            Some(value) => value.clone(),
+            None => {
+                missing = Some(key.to_string());
+                String::new()
+            }
+        }
+    });
+    match missing {
+        Some(key) => Err(anyhow!("Missing data {key} for argument {arg}")),
+        None => Ok(resolved.to_string()),
+    }
+}
+
+/// The maven name in `[group:artifact:version]`.
+fn maven_name(value: &str) -> Option<&str> {
+    value.strip_prefix('[').and_then(|v| v.strip_suffix(']'))
+}
+
+fn library_path(minecraft: &MinecraftLocation, name: &str) -> String {
+    minecraft
+        .get_library_by_path(LibraryInfo::from_name(name).path)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Read the `Main-Class` in the manifest of the jar.
+fn main_class(jar: &Path) -> Result<String> {
+    let mut zip = ZipArchive::new(File::open(jar)?)?;
+    let mut manifest = String::new();
+    zip.by_name("META-INF/MANIFEST.MF")?
+        .read_to_string(&mut manifest)?;
+    manifest
+        .lines()
+        .find_map(|line| line.strip_prefix("Main-Class:"))
+        .map(|main_class| main_class.trim().to_string())
+        .ok_or(anyhow!("No Main-Class in {}", jar.display()))
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::{io::Write, os::unix::fs::PermissionsExt};
+
+    use serde_json::json;
+    use sha1::{Digest, Sha1};
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    fn write_jar(path: &Path, entries: &[(&str, &[u8])]) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        for (name, content) in entries {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn run_client_processors() {
+        let root = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let minecraft = MinecraftLocation::new(&root);
+        let installer = root.join("forge-installer.jar");
+        write_jar(&installer, &[("data/input.bin", b"patched client")]);
+        let vanilla = minecraft.get_version_jar("1.20.1", None);
+        std::fs::create_dir_all(vanilla.parent().unwrap()).unwrap();
+        std::fs::write(&vanilla, b"vanilla client").unwrap();
+        write_jar(
+            &minecraft.get_library_by_path(LibraryInfo::from_name("com.example:copier:1.0").path),
+            &[(
+                "META-INF/MANIFEST.MF",
+                b"Manifest-Version: 1.0\r\nMain-Class: com.example.Copier\r\n",
+            )],
+        );
+
+        // records the main class and copies the first argument to the second one
+        let log = root.join("java.log");
+        let java = root.join("java");
+        std::fs::write(
+            &java,
+            format!(
+                "#!/bin/sh\necho \"$3\" >> '{}'\nshift 3\nmkdir -p \"$(dirname \"$2\")\"\ncp \"$1\" \"$2\"\n",
+                log.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&java, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let sha1 = format!("{:x}", Sha1::digest(b"patched client"));
+        let profile = |sha1: &str| -> InstallProfile {
+            serde_json::from_value(json!({
+                "minecraft": "1.20.1",
+                "path": null,
+                "libraries": [],
+                "data": {
+                    "INPUT": { "client": "/data/input.bin", "server": "/data/server.bin" },
+                    "OUTPUT_SHA": { "client": format!("'{sha1}'"), "server": "''" },
+                },
+                "processors": [
+                    {
+                        "jar": "com.example:copier:1.0",
+                        "classpath": ["com.example:lib:1.0"],
+                        "args": ["{INPUT}", "[com.example:output:1.0:client]"],
+                        "outputs": { "[com.example:output:1.0:client]": "{OUTPUT_SHA}" },
+                    },
+                    {
+                        "jar": "com.example:missing:1.0",
+                        "classpath": [],
+                        "args": ["{MINECRAFT_JAR}"],
+                        "sides": ["server"],
+                    },
+                ],
+            }))
+            .unwrap()
+        };
+        let output = minecraft.get_library_by_path("com/example/output/1.0/output-1.0-client.jar");
+        let java = java.to_string_lossy().to_string();
+
+        run_processors(&profile(&sha1), &installer, &minecraft, &java)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), b"patched client");
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "com.example.Copier\n"
+        );
+
+        // the output is already there
+        run_processors(&profile(&sha1), &installer, &minecraft, &java)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&log).unwrap().lines().count(), 1);
+
+        let bad = "0".repeat(40);
+        let err = run_processors(&profile(&bad), &installer, &minecraft, &java)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("bad output"), "{err}");
+        assert!(!output.exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -16,10 +16,19 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::collections::HashMap;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::{core::source, utils::maven::metadata_versions};
+
+use super::{
+    install::forge_installer_path, ForgeVersion, ForgeVersionInstaller, ForgeVersionType,
+    ForgeVersionUniversal, DEFAULT_FORGE_MAVEN, FORGE_ARTIFACT, PROMOTIONS_URL,
+};
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct ForgeVersionListItem {
     pub _id: String,
@@ -44,14 +53,14 @@ pub struct ForgeVersionList(Vec<ForgeVersionListItem>);
 
 impl ForgeVersionList {
     pub async fn new() -> Result<Self> {
-        Ok(reqwest::get("https://bmclapi2.bangbang93.com/forge/list/0")
+        Ok(source::get("https://bmclapi2.bangbang93.com/forge/list/0")
             .await?
             .json::<Self>()
             .await?)
     }
 
     pub async fn from_mcversion(mcversion: &str) -> Result<Self> {
-        Ok(reqwest::get(format!(
+        Ok(source::get(&format!(
             "https://bmclapi2.bangbang93.com/forge/minecraft/{mcversion}"
         ))
         .await?
@@ -60,6 +69,90 @@ impl ForgeVersionList {
     }
 }
 
+/// `promotions_slim.json`, the keys of `promos` are like `1.20.1-recommended` and
+/// `1.20.1-latest`, and the values are the forge versions.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ForgePromotions {
+    pub homepage: Option<String>,
+    pub promos: HashMap<String, String>,
+}
+
+impl ForgePromotions {
+    pub fn version_type(&self, mcversion: &str, version: &str) -> ForgeVersionType {
+        let promoted = |marker: &str| {
+            self.promos
+                .get(&format!("{mcversion}-{marker}"))
+                .map(String::as_str)
+                == Some(version)
+        };
+        if promoted("recommended") {
+            ForgeVersionType::Recommended
+        } else if promoted("latest") {
+            ForgeVersionType::Latest
+        } else {
+            ForgeVersionType::Common
+        }
+    }
+}
+
+/// All the versions of forge in the official maven, in the order of the maven metadata.
+pub async fn get_forge_version_list() -> Result<Vec<ForgeVersion>> {
+    get_forge_version_list_from(DEFAULT_FORGE_MAVEN, PROMOTIONS_URL).await
+}
+
+pub async fn get_forge_version_list_from_mcversion(mcversion: &str) -> Result<Vec<ForgeVersion>> {
+    Ok(get_forge_version_list()
+        .await?
+        .into_iter()
+        .filter(|version| version.mcversion == mcversion)
+        .collect())
+}
+
+async fn get_forge_version_list_from(
+    maven: &str,
+    promotions_url: &str,
+) -> Result<Vec<ForgeVersion>> {
+    let url = format!(
+        "{maven}/{}/maven-metadata.xml",
+        FORGE_ARTIFACT.replace(['.', ':'], "/")
+    );
+    let versions = metadata_versions(&source::get(&url).await?.text().await?);
+    let promotions: ForgePromotions = source::get(promotions_url).await?.json().await?;
+    Ok(versions
+        .iter()
+        .filter_map(|maven_version| forge_version(maven_version, &promotions))
+        .collect())
+}
+
+/// Split the maven version like `1.7.10-10.13.4.1614-1.7.10` into the Minecraft version and
+/// the forge version.
+fn forge_version(maven_version: &str, promotions: &ForgePromotions) -> Option<ForgeVersion> {
+    let mut parts = maven_version.split('-');
+    let mcversion = parts.next()?.to_string();
+    let version = parts.next()?.to_string();
+    let installer = forge_installer_path(&mcversion, maven_version);
+    // the zips before 1.5.2 are the universal ones, or there is no universal one
+    let universal = match installer.strip_suffix("-installer.jar") {
+        Some(base) => format!("{base}-universal.jar"),
+        None => installer.clone(),
+    };
+    Some(ForgeVersion {
+        installer: ForgeVersionInstaller {
+            md5: None,
+            sha1: None,
+            path: installer,
+        },
+        universal: ForgeVersionUniversal {
+            md5: None,
+            sha1: None,
+            path: universal,
+        },
+        r#type: promotions.version_type(&mcversion, &version),
+        mcversion,
+        version,
+    })
+}
+
 // #[tokio::test]
 // async fn test123123123() {
 //     // let minecraft_version = "1.20.1"
@@ -68,3 +161,90 @@ impl ForgeVersionList {
 //     tokio::fs::write("1.json", a).await.unwrap();
 //     println!("{:#?}", forge_version_list);
 // }
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::install::forge::RequiredVersion;
+    use crate::utils::test_server::serve_files;
+
+    #[tokio::test]
+    async fn forge_versions_from_maven() {
+        let versions = [
+            "1.2.5-3.4.9.171",
+            "1.4.7-6.6.2.534",
+            "1.7.2-10.12.2.1161-mc172",
+            "1.20.1-47.1.0",
+            "1.20.1-47.2.0",
+            "1.20.1-47.2.1",
+        ];
+        let metadata = format!(
+            "<metadata><versioning><versions>{}</versions></versioning></metadata>",
+            versions
+                .map(|version| format!("<version>{version}</version>"))
+                .concat()
+        );
+        let promotions = r#"{
+            "homepage": "https://files.minecraftforge.net/net/minecraftforge/forge/",
+            "promos": { "1.20.1-recommended": "47.1.0", "1.20.1-latest": "47.2.1" }
+        }"#;
+        let remote = serve_files(Arc::new(Mutex::new(HashMap::from([
+            (
+                "/net/minecraftforge/forge/maven-metadata.xml".to_string(),
+                metadata.into_bytes(),
+            ),
+            (
+                "/promotions_slim.json".to_string(),
+                promotions.to_string().into_bytes(),
+            ),
+        ]))))
+        .await;
+
+        let list = get_forge_version_list_from(&remote, &format!("{remote}/promotions_slim.json"))
+            .await
+            .unwrap();
+        assert_eq!(list.len(), versions.len());
+
+        let types: Vec<_> = list[3..]
+            .iter()
+            .map(|version| version.r#type.clone())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ForgeVersionType::Recommended,
+                ForgeVersionType::Common,
+                ForgeVersionType::Latest
+            ]
+        );
+        assert_eq!(list[3].mcversion, "1.20.1");
+        assert_eq!(list[3].version, "47.1.0");
+        assert_eq!(
+            list[3].installer.path,
+            "net/minecraftforge/forge/1.20.1-47.1.0/forge-1.20.1-47.1.0-installer.jar"
+        );
+        assert_eq!(
+            list[3].universal.path,
+            "net/minecraftforge/forge/1.20.1-47.1.0/forge-1.20.1-47.1.0-universal.jar"
+        );
+
+        let legacy = list[2].clone();
+        assert_eq!(legacy.version, "10.12.2.1161");
+        assert_eq!(
+            legacy.installer.path,
+            "net/minecraftforge/forge/1.7.2-10.12.2.1161-mc172/forge-1.7.2-10.12.2.1161-mc172-installer.jar"
+        );
+        let required: RequiredVersion = legacy.into();
+        assert_eq!(required.mcversion, "1.7.2");
+        assert_eq!(
+            list[1].installer.path,
+            "net/minecraftforge/forge/1.4.7-6.6.2.534/forge-1.4.7-6.6.2.534-universal.zip"
+        );
+        assert_eq!(
+            list[0].installer.path,
+            "net/minecraftforge/forge/1.2.5-3.4.9.171/forge-1.2.5-3.4.9.171-client.zip"
+        );
+    }
+}
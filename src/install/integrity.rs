@@ -0,0 +1,413 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Check the files of an installed version and repair them
+//!
+//! [`check_integrity`] only reads the `.minecraft` folder, it works offline. The asset objects are
+//! listed from the local asset index, so if the index itself is broken, check again after the
+//! repair to find the broken objects.
+//!
+//! # Example
+//!
+//! ```
+//! use aml_core::core::{folder::MinecraftLocation, version::ResolvedVersion};
+//! use aml_core::install::integrity::check_integrity;
+//!
+//! async fn fn_name(version: ResolvedVersion) {
+//!     let minecraft = MinecraftLocation::new(".minecraft");
+//!     let report = check_integrity(&version, &minecraft).await.unwrap();
+//!     for issue in &report.issues {
+//!         println!("{:?} {}: {:?}", issue.category, issue.download.file.display(), issue.problem);
+//!     }
+//!     if !report.is_ok() {
+//!         report.repair().await.unwrap();
+//!     }
+//! }
+//! ```
+
+use anyhow::Result;
+use futures::{stream, StreamExt};
+use serde_json::Value;
+
+use crate::{
+    core::{
+        folder::MinecraftLocation,
+        version::{AssetIndexObject, ResolvedVersion},
+        Download,
+    },
+    utils::download::{file_sha1, DownloadProgress, Downloader},
+};
+
+/// How many files are hashed at the same time.
+const CONCURRENCY: usize = 16;
+
+/// What a file is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileCategory {
+    ClientJar,
+    Library,
+    Native,
+    AssetIndex,
+    Asset,
+    LogConfig,
+}
+
+/// What is wrong with a file
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileProblem {
+    Missing,
+    SizeMismatch { expected: u64, actual: u64 },
+    Sha1Mismatch { expected: String, actual: String },
+}
+
+/// A missing or corrupt file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileIssue {
+    pub category: FileCategory,
+
+    /// Where the file is and where to download it again.
+    pub download: Download,
+    pub problem: FileProblem,
+}
+
+/// The result of [`check_integrity`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IntegrityReport {
+    /// The number of checked files.
+    pub checked: usize,
+    pub issues: Vec<FileIssue>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Download the files of the issues again.
+    ///
+    /// Libraries without url are generated by the mod loader installer and can't be downloaded,
+    /// they are skipped, install the mod loader again to repair them.
+    pub async fn repair(&self) -> Result<DownloadProgress> {
+        let downloads = self
+            .issues
+            .iter()
+            .filter(|issue| !issue.download.url.is_empty())
+            .map(|issue| issue.download.clone())
+            .collect();
+        Downloader::default()
+            .download_all(downloads)
+            .await
+            .into_result()
+    }
+}
+
+struct ExpectedFile {
+    category: FileCategory,
+    download: Download,
+    size: Option<u64>,
+}
+
+/// Check the client jar, the libraries and natives, the asset index and objects, and the log
+/// config of `version` by their size and sha1.
+pub async fn check_integrity(
+    version: &ResolvedVersion,
+    minecraft: &MinecraftLocation,
+) -> Result<IntegrityReport> {
+    let mut expected = Vec::new();
+    if let Some(client) = version
+        .downloads
+        .as_ref()
+        .and_then(|downloads| downloads.get("client"))
+    {
+        let id = &version.id;
+        expected.push(ExpectedFile {
+            category: FileCategory::ClientJar,
+            download: Download {
+                url: client.url.clone(),
+                file: minecraft.versions.join(format!("{id}/{id}.jar")),
+                sha1: Some(client.sha1.clone()),
+            },
+            size: Some(client.size),
+        });
+    }
+    for library in &version.libraries {
+        expected.push(ExpectedFile {
+            category: if library.is_native_library {
+                FileCategory::Native
+            } else {
+                FileCategory::Library
+            },
+            download: Download {
+                url: library.download_info.url.clone(),
+                file: minecraft.libraries.join(&library.download_info.path),
+                sha1: library.download_info.sha1.clone(),
+            },
+            size: library.download_info.size,
+        });
+    }
+    if let Some(logging) = version
+        .logging
+        .as_ref()
+        .and_then(|logging| logging.get("client"))
+    {
+        expected.push(ExpectedFile {
+            category: FileCategory::LogConfig,
+            download: Download {
+                url: logging.file.url.clone(),
+                file: minecraft.get_log4j2_configuration(&version.id),
+                sha1: Some(logging.file.sha1.clone()),
+            },
+            size: Some(logging.file.size),
+        });
+    }
+    if let Some(asset_index) = &version.asset_index {
+        let index_path = minecraft.get_assets_index(&asset_index.id);
+        expected.push(ExpectedFile {
+            category: FileCategory::AssetIndex,
+            download: Download {
+                url: asset_index.url.clone(),
+                file: index_path.clone(),
+                sha1: asset_index.sha1.clone(),
+            },
+            size: Some(asset_index.size),
+        });
+        let objects = tokio::fs::read_to_string(&index_path)
+            .await
+            .ok()
+            .and_then(|index| serde_json::from_str::<Value>(&index).ok())
+            .and_then(|index| {
+                serde_json::from_value::<AssetIndexObject>(index["objects"].clone()).ok()
+            })
+            .unwrap_or_default();
+        // a broken index may have anything as hash
+        let objects = objects
+            .into_values()
+            .filter(|object| object.hash.len() == 40);
+        expected.extend(objects.map(|object| {
+            let prefix = &object.hash[0..2];
+            ExpectedFile {
+                category: FileCategory::Asset,
+                download: Download {
+                    url: format!(
+                        "https://resources.download.minecraft.net/{prefix}/{}",
+                        object.hash
+                    ),
+                    file: minecraft
+                        .assets
+                        .join("objects")
+                        .join(prefix)
+                        .join(&object.hash),
+                    sha1: Some(object.hash.clone()),
+                },
+                size: Some(object.size as u64),
+            }
+        }));
+    }
+
+    let checked = expected.len();
+    let issues = stream::iter(expected)
+        .map(check_file)
+        .buffer_unordered(CONCURRENCY)
+        .filter_map(|issue| async { issue })
+        .collect()
+        .await;
+    Ok(IntegrityReport { checked, issues })
+}
+
+async fn check_file(expected: ExpectedFile) -> Option<FileIssue> {
+    let issue = |problem| {
+        Some(FileIssue {
+            category: expected.category,
+            download: expected.download.clone(),
+            problem,
+        })
+    };
+    let metadata = match tokio::fs::metadata(&expected.download.file).await {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => return issue(FileProblem::Missing),
+    };
+    if let Some(size) = expected.size {
+        if metadata.len() != size {
+            return issue(FileProblem::SizeMismatch {
+                expected: size,
+                actual: metadata.len(),
+            });
+        }
+    }
+    if let Some(sha1) = &expected.download.sha1 {
+        let actual = match file_sha1(&expected.download.file).await {
+            Ok(actual) => actual,
+            Err(_) => return issue(FileProblem::Missing),
+        };
+        if !actual.eq_ignore_ascii_case(sha1) {
+            return issue(FileProblem::Sha1Mismatch {
+                expected: sha1.clone(),
+                actual,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::{version::Version, OsType, PlatformInfo};
+    use crate::utils::test_server::{serve_files, sha1, Files};
+
+    fn write(path: &std::path::Path, data: &[u8]) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, data).unwrap();
+    }
+
+    #[tokio::test]
+    async fn check_and_repair_version() {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let platform = PlatformInfo {
+            arch: "x64".to_string(),
+            name: "linux".to_string(),
+            os_type: OsType::Linux,
+            version: String::new(),
+        };
+        let files = Files::default();
+        let base = serve_files(files.clone()).await;
+
+        let client = b"client jar".to_vec();
+        let library = b"library jar".to_vec();
+        let native = b"native jar".to_vec();
+        let log_config = b"<Configuration/>".to_vec();
+        let (asset_a, asset_b) = (b"sound".to_vec(), b"texture".to_vec());
+        let asset_index = json!({"objects": {
+            "minecraft/sounds/a.ogg": {"hash": sha1(&asset_a), "size": asset_a.len()},
+            "minecraft/textures/b.png": {"hash": sha1(&asset_b), "size": asset_b.len()}
+        }})
+        .to_string()
+        .into_bytes();
+        let artifact = |path: &str, data: &[u8]| json!({"path": path, "sha1": sha1(data), "size": data.len(), "url": format!("{base}/{path}")});
+        let version_json = json!({
+            "id": "1.12.2",
+            "type": "release",
+            "mainClass": "net.minecraft.client.main.Main",
+            "minecraftArguments": "--username ${auth_player_name}",
+            "assets": "1.12",
+            "assetIndex": {
+                "id": "1.12",
+                "sha1": sha1(&asset_index),
+                "size": asset_index.len(),
+                "totalSize": 0,
+                "url": format!("{base}/1.12.json")
+            },
+            "downloads": {"client": {
+                "sha1": sha1(&client),
+                "size": client.len(),
+                "url": format!("{base}/client.jar")
+            }},
+            "libraries": [
+                {
+                    "name": "com.example:library:1.0",
+                    "downloads": {"artifact": artifact("library.jar", &library)}
+                },
+                {
+                    "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4",
+                    "natives": {"linux": "natives-linux"},
+                    "downloads": {"classifiers": {"natives-linux": artifact("native.jar", &native)}}
+                }
+            ],
+            "logging": {"client": {
+                "argument": "-Dlog4j.configurationFile=${path}",
+                "file": {
+                    "id": "client-1.12.xml",
+                    "sha1": sha1(&log_config),
+                    "size": log_config.len(),
+                    "url": format!("{base}/client-1.12.xml")
+                },
+                "type": "log4j2-xml"
+            }}
+        });
+        let version = Version::from_str(&version_json.to_string())
+            .unwrap()
+            .parse(&minecraft, &platform)
+            .await
+            .unwrap();
+
+        write(&minecraft.versions.join("1.12.2/1.12.2.jar"), &client);
+        // same size, other content
+        write(&minecraft.libraries.join("library.jar"), b"library JAR");
+        write(&minecraft.get_assets_index("1.12"), &asset_index);
+        let objects = minecraft.assets.join("objects");
+        write(
+            &objects.join(&sha1(&asset_a)[0..2]).join(sha1(&asset_a)),
+            &asset_a,
+        );
+        write(
+            &objects.join(&sha1(&asset_b)[0..2]).join(sha1(&asset_b)),
+            b"tex",
+        );
+        write(&minecraft.get_log4j2_configuration("1.12.2"), &log_config);
+
+        let mut report = check_integrity(&version, &minecraft).await.unwrap();
+        assert_eq!(report.checked, 7);
+        report.issues.sort_by_key(|issue| issue.category as usize);
+        let problems: Vec<_> = report
+            .issues
+            .iter()
+            .map(|issue| (issue.category, issue.problem.clone()))
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                (
+                    FileCategory::Library,
+                    FileProblem::Sha1Mismatch {
+                        expected: sha1(&library),
+                        actual: sha1(b"library JAR"),
+                    }
+                ),
+                (FileCategory::Native, FileProblem::Missing),
+                (
+                    FileCategory::Asset,
+                    FileProblem::SizeMismatch {
+                        expected: 7,
+                        actual: 3,
+                    }
+                ),
+            ]
+        );
+
+        // only the broken files are downloaded, the asset objects are not served here
+        {
+            let mut files = files.lock().unwrap();
+            files.insert("/library.jar".to_string(), library);
+            files.insert("/native.jar".to_string(), native);
+        }
+        report
+            .issues
+            .retain(|issue| issue.category != FileCategory::Asset);
+        let progress = report.repair().await.unwrap();
+        assert_eq!(progress.total, 2);
+        let report = check_integrity(&version, &minecraft).await.unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].category, FileCategory::Asset);
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
+    }
+}
@@ -16,53 +16,42 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
 use anyhow::{anyhow, Result};
-use reqwest::Url;
 use serde_json::Value;
-use tokio::io::AsyncWriteExt;
 
+use crate::core::task::TaskEventListeners;
 use crate::core::version::ResolvedLibrary;
 use crate::core::Download;
 use crate::core::{
     folder::MinecraftLocation,
+    source,
     version::{self, AssetIndex, AssetIndexObject, ResolvedVersion, VersionManifest},
     PlatformInfo,
 };
+use crate::utils::download::{check_file_sha1, file_sha1, Downloader};
 
 pub mod fabric;
 pub mod forge;
+pub mod integrity;
+pub mod neoforge;
 // pub mod optifine;
 pub mod quilt;
 
-/// todo
-pub struct NetworkOptions {
-    pub use_proxy: bool,
-    pub minecraft_remote: String,
-    pub forge_remote: String,
-    pub fabric_remote: String,
-    pub optifine_remote: String,
-    pub quilt_remote: String,
-}
+pub use crate::core::source::NetworkOptions;
 
 pub(crate) fn generate_libraries_downloads(
-    libraries: &Vec<ResolvedLibrary>,
+    libraries: &[ResolvedLibrary],
     minecraft_location: &MinecraftLocation,
 ) -> Vec<Download> {
     libraries
-        .clone()
-        .into_iter()
+        .iter()
+        // libraries without url are generated by the mod loader installer
+        .filter(|library| !library.download_info.url.is_empty())
+        .cloned()
         .map(|library| Download {
-            url: if library.is_native_library {
-                println!("find native library url: {}", &library.download_info.url);
-                library.download_info.url
-            } else {
-                format!(
-                    "https://download.mcbbs.net/maven/{}",
-                    library.download_info.path
-                )
-            },
+            url: library.download_info.url,
             file: minecraft_location
                 .libraries
                 .join(library.download_info.path),
@@ -71,20 +60,46 @@ pub(crate) fn generate_libraries_downloads(
         .collect()
 }
 
+/// Save the asset index, then list the asset objects.
+///
+/// An asset index which is already there with the right sha1 is not fetched again.
 pub(crate) async fn generate_assets_downloads(
     asset_index: AssetIndex,
     minecraft_location: &MinecraftLocation,
 ) -> Result<Vec<Download>> {
-    let asset_index_url = Url::parse((&asset_index.url).as_ref())?;
-    let asset_index_raw = reqwest::get(asset_index_url).await?.text().await?;
-    let asset_index_json: Value = serde_json::from_str((&asset_index_raw).as_ref())?;
+    let index_path = minecraft_location.get_assets_index(&asset_index.id);
+    let up_to_date = match &asset_index.sha1 {
+        Some(sha1) => check_file_sha1(&index_path, sha1).await,
+        None => false,
+    };
+    let asset_index_raw = if up_to_date {
+        tokio::fs::read_to_string(&index_path).await?
+    } else {
+        let asset_index_raw = source::get(&asset_index.url).await?.bytes().await?;
+        if let Some(sha1) = &asset_index.sha1 {
+            let actual = format!(
+                "{:x}",
+                <sha1::Sha1 as sha1::Digest>::digest(&asset_index_raw)
+            );
+            if !actual.eq_ignore_ascii_case(sha1) {
+                return Err(anyhow!(
+                    "sha1 mismatch of asset index {}: expected {sha1}, got {actual}",
+                    asset_index.id
+                ));
+            }
+        }
+        tokio::fs::create_dir_all(index_path.parent().unwrap()).await?;
+        tokio::fs::write(&index_path, &asset_index_raw).await?;
+        String::from_utf8(asset_index_raw.to_vec())?
+    };
+    let asset_index_json: Value = serde_json::from_str(asset_index_raw.as_ref())?;
     let asset_index_object: AssetIndexObject =
         serde_json::from_value(asset_index_json["objects"].clone())?;
-    let mut assets: Vec<_> = asset_index_object
+    Ok(asset_index_object
         .into_iter()
         .map(|obj| Download {
             url: format!(
-                "https://download.mcbbs.net/assets/{}/{}",
+                "https://resources.download.minecraft.net/{}/{}",
                 &obj.1.hash[0..2],
                 obj.1.hash
             ),
@@ -95,42 +110,38 @@ pub(crate) async fn generate_assets_downloads(
                 .join(&obj.1.hash),
             sha1: Some(obj.1.hash),
         })
-        .collect();
-    assets.push(Download {
-        url: asset_index.url,
-        file: minecraft_location
-            .assets
-            .join("indexes")
-            .join(format!("{}.json", asset_index.id)),
-        sha1: None,
-    });
-    Ok(assets)
+        .collect())
 }
 
-/// check game integrity and try to repair files
-///
-/// This is usually done in situations where the integrity of the game is uncertain,
-/// such as launching for the first time after installation
+/// List the libraries, assets and the log config of a version, downloading the asset index if
+/// needed. To find the broken files of an installed version offline, see
+/// [`integrity::check_integrity`].
 pub async fn generate_dependencies_downloads(
-    version: ResolvedVersion,
-    minecraft_location: MinecraftLocation,
-) -> Result<()> {
+    version: &ResolvedVersion,
+    minecraft_location: &MinecraftLocation,
+) -> Result<Vec<Download>> {
     let mut download_list = Vec::new();
 
     download_list.extend(generate_libraries_downloads(
         &version.libraries,
-        &minecraft_location,
+        minecraft_location,
     ));
     download_list.extend(
-        generate_assets_downloads(version.asset_index.clone().unwrap(), &minecraft_location)
-            .await?,
+        generate_assets_downloads(
+            version
+                .asset_index
+                .clone()
+                .ok_or(anyhow!("No asset index found"))?,
+            minecraft_location,
+        )
+        .await?,
     );
-    let log4j2 = generate_log4j2_configuration_download(&version, &minecraft_location);
+    let log4j2 = generate_log4j2_configuration_download(version, minecraft_location);
     if let Ok(log4j2) = log4j2 {
         download_list.push(log4j2);
     }
 
-    Ok(())
+    Ok(download_list)
 }
 
 pub fn generate_log4j2_configuration_download(
@@ -144,91 +155,327 @@ pub fn generate_log4j2_configuration_download(
         .clone();
     Ok(Download {
         url: logging_client.file.url,
-        file: minecraft_location
-            .get_version_root(version.id.clone())
-            .join("log4j2.xml"),
+        file: minecraft_location.get_log4j2_configuration(&version.id),
         sha1: Some(logging_client.file.sha1),
     })
 }
 
+/// Save the version.json of `version_id` unless it is up to date, then resolve it.
+async fn resolve_version(
+    version_id: &str,
+    manifest: Result<VersionManifest>,
+    minecraft_location: &MinecraftLocation,
+    platform: &PlatformInfo,
+) -> Result<ResolvedVersion> {
+    let version_json_path = minecraft_location.get_version_json(version_id);
+    let installed = tokio::fs::try_exists(&version_json_path).await?;
+    let version_json_raw = match manifest {
+        Ok(manifest) => {
+            let version_metadata = manifest.find(version_id)?;
+            let up_to_date = installed
+                && file_sha1(&version_json_path).await.ok().as_ref()
+                    == Some(&version_metadata.sha1);
+            if up_to_date {
+                tokio::fs::read_to_string(&version_json_path).await?
+            } else {
+                let version_json_raw = source::get(&version_metadata.url).await?.text().await?;
+                tokio::fs::create_dir_all(version_json_path.parent().unwrap()).await?;
+                tokio::fs::write(&version_json_path, &version_json_raw).await?;
+                version_json_raw
+            }
+        }
+        // offline and no cached manifest, but the version is already installed
+        Err(_) if installed => tokio::fs::read_to_string(&version_json_path).await?,
+        Err(err) => return Err(err),
+    };
+    version::Version::from_str(&version_json_raw)?
+        .parse(minecraft_location, platform)
+        .await
+}
+
+/// The client jar of a resolved version.
+fn generate_client_download(
+    version: &ResolvedVersion,
+    minecraft_location: &MinecraftLocation,
+) -> Result<Download> {
+    let id = &version.id;
+    let client = version
+        .downloads
+        .as_ref()
+        .and_then(|downloads| downloads.get("client"))
+        .ok_or(anyhow!("No client download found"))?;
+    Ok(Download {
+        url: client.url.clone(),
+        file: minecraft_location.versions.join(format!("{id}/{id}.jar")),
+        sha1: Some(client.sha1.clone()),
+    })
+}
+
 pub async fn generate_download_info(
     version_id: &str,
     minecraft_location: MinecraftLocation,
     platform: &PlatformInfo,
 ) -> Result<Vec<Download>> {
-    let versions = VersionManifest::new().await?.versions;
-    let version_metadata: Vec<_> = versions
-        .into_iter()
-        .filter(|v| v.id == version_id)
-        .collect();
-    if version_metadata.len() != 1 {
-        panic!("Bad version manifest!!!")
-    };
-    let version_metadata = version_metadata.get(0).unwrap();
-
-    let version_json_raw = reqwest::get(version_metadata.url.clone())
-        .await?
-        .text()
-        .await?;
-    let version = version::Version::from_str(&version_json_raw)?
-        .parse(&minecraft_location, &platform)
-        .await?;
-    let id = &version.id;
+    let manifest = VersionManifest::cached(&minecraft_location).await;
+    let version = resolve_version(version_id, manifest, &minecraft_location, platform).await?;
+    let mut download_list = vec![generate_client_download(&version, &minecraft_location)?];
+    download_list.extend(generate_dependencies_downloads(&version, &minecraft_location).await?);
+    Ok(download_list)
+}
 
-    let version_json_path = minecraft_location.versions.join(format!("{id}/{id}.json"));
-    tokio::fs::create_dir_all(version_json_path.parent().unwrap()).await?;
-    let mut file = tokio::fs::File::create(&version_json_path).await?;
-    file.write_all(version_json_raw.as_bytes()).await?;
+/// Install a vanilla version: the version.json, the client jar, the libraries, the asset index
+/// and objects, and the log config. Every file is checked with its sha1.
+///
+/// The progress is reported in two steps, step `1` is the version.json and the asset index
+/// (`0/1` then `1/1`), step `2` counts the files.
+///
+/// # Example
+///
+/// ```
+/// use aml_core::core::{folder::MinecraftLocation, task::TaskEventListeners};
+/// use aml_core::install::install_vanilla;
+///
+/// async fn fn_name() {
+///     let minecraft = MinecraftLocation::new(".minecraft");
+///     let version = install_vanilla("1.20.1", minecraft, TaskEventListeners::default())
+///         .await
+///         .unwrap();
+///     println!("{} installed", version.id);
+/// }
+/// ```
+pub async fn install_vanilla(
+    version_id: &str,
+    minecraft_location: MinecraftLocation,
+    listeners: TaskEventListeners,
+) -> Result<ResolvedVersion> {
+    let platform = PlatformInfo::new().await;
+    let manifest = VersionManifest::cached(&minecraft_location).await;
+    install_vanilla_with(
+        version_id,
+        manifest,
+        minecraft_location,
+        &platform,
+        listeners,
+    )
+    .await
+}
 
-    let mut download_list = vec![];
-    download_list.push(Download {
-        url: format!("https://download.mcbbs.net/version/{version_id}/client"),
-        file: minecraft_location.versions.join(format!("{id}/{id}.jar")),
-        sha1: None,
-    });
+async fn install_vanilla_with(
+    version_id: &str,
+    manifest: Result<VersionManifest>,
+    minecraft_location: MinecraftLocation,
+    platform: &PlatformInfo,
+    listeners: TaskEventListeners,
+) -> Result<ResolvedVersion> {
+    let listeners = Arc::new(listeners);
+    listeners.start();
+    let progress_listeners = listeners.clone();
+    let result = async {
+        listeners.progress(0, 1, 1);
+        let version = resolve_version(version_id, manifest, &minecraft_location, platform).await?;
+        let mut download_list = vec![generate_client_download(&version, &minecraft_location)?];
+        download_list.extend(generate_dependencies_downloads(&version, &minecraft_location).await?);
+        listeners.progress(1, 1, 1);
 
-    download_list.extend(generate_libraries_downloads(
-        &version.libraries,
-        &minecraft_location,
-    ));
-    download_list.extend(
-        generate_assets_downloads(
-            version
-                .asset_index
-                .clone()
-                .ok_or(std::io::Error::from(std::io::ErrorKind::NotFound))?,
-            &minecraft_location,
-        )
-        .await?,
-    );
-    let log4j2 = generate_log4j2_configuration_download(&version, &minecraft_location);
-    if let Ok(log4j2) = log4j2 {
-        download_list.push(log4j2);
+        Downloader::default()
+            .on_progress(Box::new(move |progress| {
+                progress_listeners.progress(progress.completed, progress.total, 2)
+            }))
+            .download_all(download_list)
+            .await
+            .into_result()?;
+        Ok(version)
     }
-    Ok(download_list)
+    .await;
+    match result {
+        Ok(_) => listeners.succeed(),
+        Err(_) => listeners.failed(),
+    }
+    result
 }
 
 #[cfg(test)]
 mod test {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
+
+    use serde_json::json;
+
     use super::*;
     use crate::core::folder::MinecraftLocation;
-    use crate::core::HTTP_CLIENT;
+    use crate::core::OsType;
+    use crate::utils::download::Downloader;
+    use crate::utils::test_server::{serve_files, sha1, Files};
     #[tokio::test]
     async fn test() {
         let platform = PlatformInfo::new().await;
         let downloads = generate_download_info("1.19.3", MinecraftLocation::new("test"), &platform)
             .await
             .unwrap();
-        for (index, download) in downloads.into_iter().enumerate() {
-            println!("{}", index);
-            let mut response = HTTP_CLIENT.get(download.url).send().await.unwrap();
-            tokio::fs::create_dir_all(download.file.parent().unwrap())
+        Downloader::default()
+            .on_progress(Box::new(|progress| {
+                println!("{}/{}", progress.completed, progress.total)
+            }))
+            .download_all(downloads)
+            .await
+            .into_result()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn install_vanilla_version() {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let platform = PlatformInfo {
+            arch: "x64".to_string(),
+            name: "linux".to_string(),
+            os_type: OsType::Linux,
+            version: String::new(),
+        };
+        let files = Files::default();
+        let base = serve_files(files.clone()).await;
+
+        let client = b"client jar".to_vec();
+        let library = b"library jar".to_vec();
+        let log_config = b"<Configuration/>".to_vec();
+        let asset_index = json!({"objects": {}}).to_string().into_bytes();
+        let version_json = json!({
+            "id": "1.20.1",
+            "type": "release",
+            "mainClass": "net.minecraft.client.main.Main",
+            "minecraftArguments": "--username ${auth_player_name}",
+            "assets": "5",
+            "assetIndex": {
+                "id": "5",
+                "sha1": sha1(&asset_index),
+                "size": asset_index.len(),
+                "totalSize": 0,
+                "url": format!("{base}/asset_index.json")
+            },
+            "downloads": {"client": {
+                "sha1": sha1(&client),
+                "size": client.len(),
+                "url": format!("{base}/client.jar")
+            }},
+            "libraries": [{
+                "name": "com.example:library:1.0",
+                "downloads": {"artifact": {
+                    "path": "com/example/library/1.0/library-1.0.jar",
+                    "sha1": sha1(&library),
+                    "size": library.len(),
+                    "url": format!("{base}/library.jar")
+                }}
+            }],
+            "logging": {"client": {
+                "argument": "-Dlog4j.configurationFile=${path}",
+                "file": {
+                    "id": "client-1.12.xml",
+                    "sha1": sha1(&log_config),
+                    "size": log_config.len(),
+                    "url": format!("{base}/client-1.12.xml")
+                },
+                "type": "log4j2-xml"
+            }},
+            "javaVersion": {"component": "java-runtime-gamma", "majorVersion": 17}
+        })
+        .to_string()
+        .into_bytes();
+        let manifest = json!({
+            "latest": {"release": "1.20.1", "snapshot": "1.20.1"},
+            "versions": [{
+                "id": "1.20.1",
+                "type": "release",
+                "url": format!("{base}/1.20.1.json"),
+                "time": "2023-06-12T13:25:51+00:00",
+                "releaseTime": "2023-06-12T13:25:51+00:00",
+                "sha1": sha1(&version_json),
+                "complianceLevel": 1
+            }]
+        })
+        .to_string()
+        .into_bytes();
+        {
+            let mut files = files.lock().unwrap();
+            files.insert("/version_manifest_v2.json".to_string(), manifest);
+            files.insert("/1.20.1.json".to_string(), version_json.clone());
+            files.insert("/client.jar".to_string(), client.clone());
+            files.insert("/library.jar".to_string(), library.clone());
+            files.insert("/asset_index.json".to_string(), asset_index.clone());
+            files.insert("/client-1.12.xml".to_string(), log_config.clone());
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let files_done = Arc::new(AtomicUsize::new(0));
+        let listeners = {
+            let (start, succeed) = (events.clone(), events.clone());
+            let files_done = files_done.clone();
+            TaskEventListeners::default()
+                .on_start(Box::new(move || start.lock().unwrap().push("start")))
+                .on_progress(Box::new(move |completed, total, step| {
+                    if step == 2 {
+                        assert_eq!(total, 3);
+                        files_done.fetch_max(completed, Ordering::SeqCst);
+                    }
+                }))
+                .on_succeed(Box::new(move || succeed.lock().unwrap().push("succeed")))
+                .on_failed(Box::new(|| panic!("the install failed")))
+        };
+        let manifest =
+            VersionManifest::cached_from(&format!("{base}/version_manifest_v2.json"), &minecraft)
+                .await;
+        let version =
+            install_vanilla_with("1.20.1", manifest, minecraft.clone(), &platform, listeners)
                 .await
                 .unwrap();
-            let mut file = tokio::fs::File::create(download.file).await.unwrap();
-            while let Some(chunk) = response.chunk().await.unwrap() {
-                file.write_all(&chunk).await.unwrap();
-            }
-        }
+        assert_eq!(version.id, "1.20.1");
+        assert_eq!(*events.lock().unwrap(), vec!["start", "succeed"]);
+        assert_eq!(files_done.load(Ordering::SeqCst), 3);
+
+        let read = |path: std::path::PathBuf| std::fs::read(path).unwrap();
+        assert_eq!(read(minecraft.get_version_json("1.20.1")), version_json);
+        assert_eq!(read(minecraft.versions.join("1.20.1/1.20.1.jar")), client);
+        assert_eq!(
+            read(
+                minecraft
+                    .libraries
+                    .join("com/example/library/1.0/library-1.0.jar")
+            ),
+            library
+        );
+        assert_eq!(read(minecraft.get_assets_index("5")), asset_index);
+        assert_eq!(
+            read(minecraft.get_log4j2_configuration("1.20.1")),
+            log_config
+        );
+
+        // a broken file fails the install
+        files
+            .lock()
+            .unwrap()
+            .insert("/library.jar".to_string(), b"broken".to_vec());
+        std::fs::remove_file(
+            minecraft
+                .libraries
+                .join("com/example/library/1.0/library-1.0.jar"),
+        )
+        .unwrap();
+        let failed = Arc::new(AtomicUsize::new(0));
+        let failed_clone = failed.clone();
+        let listeners = TaskEventListeners::default().on_failed(Box::new(move || {
+            failed_clone.fetch_add(1, Ordering::SeqCst);
+        }));
+        assert!(install_vanilla_with(
+            "1.20.1",
+            Err(anyhow!("offline")),
+            minecraft.clone(),
+            &platform,
+            listeners
+        )
+        .await
+        .is_err());
+        assert_eq!(failed.load(Ordering::SeqCst), 1);
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
     }
 }
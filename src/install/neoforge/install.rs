@@ -0,0 +1,191 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+
+use crate::{
+    core::{folder::MinecraftLocation, Download},
+    install::forge::{install::install_from_installer, InstallForgeOptions},
+    utils::download::download,
+};
+
+use super::{NeoForgeVersion, DEFAULT_MAVEN_URL};
+
+/// Download the installer of the NeoForge version and install it.
+///
+/// The Minecraft version must be installed before, the processors of the installer patch the
+/// client jar with `options.java`.
+pub async fn install_neoforge(
+    version: NeoForgeVersion,
+    minecraft: MinecraftLocation,
+    options: Option<InstallForgeOptions>,
+) -> Result<()> {
+    install_neoforge_from(DEFAULT_MAVEN_URL, version, minecraft, options).await
+}
+
+async fn install_neoforge_from(
+    remote: &str,
+    version: NeoForgeVersion,
+    minecraft: MinecraftLocation,
+    options: Option<InstallForgeOptions>,
+) -> Result<()> {
+    let path = version.installer_path();
+    let installer = minecraft.get_library_by_path(&path);
+    download(Download {
+        url: format!("{remote}/{path}"),
+        file: installer.clone(),
+        sha1: None,
+    })
+    .await?;
+    install_from_installer(
+        &installer,
+        &version.artifact,
+        &version.version,
+        minecraft,
+        options,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    use serde_json::{json, Value};
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+    use crate::install::neoforge::version_list::get_neoforge_version_list_from_mcversion;
+    use crate::utils::test_server::{serve_files, Files};
+
+    fn metadata(versions: &[&str]) -> Vec<u8> {
+        let versions: String = versions
+            .iter()
+            .map(|version| format!("<version>{version}</version>"))
+            .collect();
+        format!("<metadata><versioning><versions>{versions}</versions></versioning></metadata>")
+            .into_bytes()
+    }
+
+    fn installer() -> Vec<u8> {
+        let profile = json!({
+            "spec": 1,
+            "profile": "NeoForge",
+            "version": "neoforge-20.4.80",
+            "path": null,
+            "minecraft": "1.20.4",
+            "data": {
+                "BINPATCH": { "client": "/data/client.lzma", "server": "/data/server.lzma" },
+            },
+            "processors": [],
+            "libraries": [],
+        });
+        let version = json!({
+            "id": "neoforge-20.4.80",
+            "inheritsFrom": "1.20.4",
+            "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+        });
+        let (profile, version) = (profile.to_string(), version.to_string());
+        let entries: [(&str, &[u8]); 4] = [
+            ("install_profile.json", profile.as_bytes()),
+            ("version.json", version.as_bytes()),
+            ("data/client.lzma", b"client patches"),
+            (
+                "maven/net/neoforged/neoforge/20.4.80/neoforge-20.4.80-universal.jar",
+                b"universal",
+            ),
+        ];
+        let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[tokio::test]
+    async fn install_neoforge_installer() {
+        let files: Files = Arc::new(Mutex::new(HashMap::from([
+            (
+                "/net/neoforged/forge/maven-metadata.xml".to_string(),
+                metadata(&["1.20.1-47.1.106"]),
+            ),
+            (
+                "/net/neoforged/neoforge/maven-metadata.xml".to_string(),
+                metadata(&["20.2.3-beta", "20.4.80"]),
+            ),
+            (
+                "/net/neoforged/neoforge/20.4.80/neoforge-20.4.80-installer.jar".to_string(),
+                installer(),
+            ),
+        ])));
+        let remote = serve_files(files).await;
+
+        let versions = get_neoforge_version_list_from_mcversion(Some(remote.clone()), "1.20.4")
+            .await
+            .unwrap();
+        assert_eq!(versions.len(), 1);
+        let version = versions[0].clone();
+        assert_eq!(version.version, "20.4.80");
+
+        let root = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let minecraft = MinecraftLocation::new(&root);
+        install_neoforge_from(&remote, version, minecraft.clone(), None)
+            .await
+            .unwrap();
+
+        assert!(minecraft.get_version_json("neoforge-20.4.80").is_file());
+        assert_eq!(
+            std::fs::read(minecraft.get_library_by_path(
+                "net/neoforged/neoforge/20.4.80/neoforge-20.4.80-universal.jar"
+            ))
+            .unwrap(),
+            b"universal"
+        );
+        assert_eq!(
+            std::fs::read(minecraft.get_library_by_path(
+                "net/neoforged/neoforge/20.4.80/neoforge-20.4.80-clientdata.lzma"
+            ))
+            .unwrap(),
+            b"client patches"
+        );
+        let profile: Value = serde_json::from_slice(
+            &std::fs::read(
+                minecraft
+                    .get_version_root("neoforge-20.4.80")
+                    .join("install_profile.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            profile["data"]["INSTALLER"]["client"],
+            "[net.neoforged:neoforge:20.4.80:installer]"
+        );
+        assert_eq!(
+            profile["data"]["BINPATCH"]["client"],
+            "[net.neoforged:neoforge:20.4.80:clientdata@lzma]"
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! NeoForge, the fork of forge since 1.20.1
+//!
+//! The installer of NeoForge has the same format as the forge installer, so it is installed by
+//! the forge machinery. Only the maven coordinates and the versioning differ: NeoForge for
+//! 1.20.1 is `net.neoforged:forge:1.20.1-47.1.106`, the later ones are
+//! `net.neoforged:neoforge:20.4.80`, where `20.4` is Minecraft 1.20.4.
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::version::LibraryInfo;
+
+pub mod install;
+pub mod version_list;
+
+const DEFAULT_MAVEN_URL: &str = "https://maven.neoforged.net/releases";
+
+/// The maven `group:artifact` of NeoForge.
+const NEOFORGE_ARTIFACT: &str = "net.neoforged:neoforge";
+
+/// The maven `group:artifact` of NeoForge for 1.20.1.
+const LEGACY_FORGE_ARTIFACT: &str = "net.neoforged:forge";
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NeoForgeVersion {
+    /// The maven `group:artifact`, `net.neoforged:neoforge`, or `net.neoforged:forge` for 1.20.1
+    pub artifact: String,
+
+    /// The maven version, like `20.4.80` or `1.20.1-47.1.106`
+    pub version: String,
+
+    /// The Minecraft version
+    pub mcversion: String,
+}
+
+impl NeoForgeVersion {
+    /// A version of `net.neoforged:neoforge`, the Minecraft version is inferred from it.
+    ///
+    /// `20.4.80` is for 1.20.4 and `21.0.0-beta` is for 1.21. Returns `None` if the version
+    /// is not for a release of Minecraft, like the April Fools ones.
+    pub fn from_neoforge(version: &str) -> Option<Self> {
+        let numbers = version
+            .split('-')
+            .next()?
+            .split('.')
+            .map(|number| number.parse::<u32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let mcversion = match numbers.as_slice() {
+            [0, ..] => return None,
+            // since Minecraft 26.1, the version starts with the Minecraft version
+            [major, minor, patch, _] => match patch {
+                0 => format!("{major}.{minor}"),
+                patch => format!("{major}.{minor}.{patch}"),
+            },
+            [major, minor, _] => match minor {
+                0 => format!("1.{major}"),
+                minor => format!("1.{major}.{minor}"),
+            },
+            _ => return None,
+        };
+        Some(Self {
+            artifact: NEOFORGE_ARTIFACT.to_string(),
+            version: version.to_string(),
+            mcversion,
+        })
+    }
+
+    /// A version of `net.neoforged:forge`, like `1.20.1-47.1.106`.
+    pub fn from_legacy_forge(version: &str) -> Option<Self> {
+        let (mcversion, _) = version.split_once('-')?;
+        Some(Self {
+            artifact: LEGACY_FORGE_ARTIFACT.to_string(),
+            version: version.to_string(),
+            mcversion: mcversion.to_string(),
+        })
+    }
+
+    pub fn is_beta(&self) -> bool {
+        self.version.contains("-beta")
+    }
+
+    /// The maven path of the installer jar.
+    pub fn installer_path(&self) -> String {
+        LibraryInfo::from_name(&format!("{}:{}:installer", self.artifact, self.version)).path
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn neoforge_versions() {
+        let version = NeoForgeVersion::from_neoforge("20.4.80").unwrap();
+        assert_eq!(version.mcversion, "1.20.4");
+        assert!(!version.is_beta());
+        assert_eq!(
+            version.installer_path(),
+            "net/neoforged/neoforge/20.4.80/neoforge-20.4.80-installer.jar"
+        );
+
+        let version = NeoForgeVersion::from_neoforge("21.0.0-beta").unwrap();
+        assert_eq!(version.mcversion, "1.21");
+        assert!(version.is_beta());
+        assert_eq!(
+            NeoForgeVersion::from_neoforge("26.1.0.1-beta")
+                .unwrap()
+                .mcversion,
+            "26.1"
+        );
+        assert_eq!(
+            NeoForgeVersion::from_neoforge("0.25w14craftmine.3-beta"),
+            None
+        );
+
+        let version = NeoForgeVersion::from_legacy_forge("1.20.1-47.1.106").unwrap();
+        assert_eq!(version.mcversion, "1.20.1");
+        assert_eq!(
+            version.installer_path(),
+            "net/neoforged/forge/1.20.1-47.1.106/forge-1.20.1-47.1.106-installer.jar"
+        );
+    }
+}
@@ -0,0 +1,63 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+
+use crate::{core::source, utils::maven::metadata_versions};
+
+use super::{NeoForgeVersion, DEFAULT_MAVEN_URL, LEGACY_FORGE_ARTIFACT, NEOFORGE_ARTIFACT};
+
+/// All the versions of NeoForge in the maven, the legacy `net.neoforged:forge` versions first,
+/// each in the order of the maven metadata.
+pub async fn get_neoforge_version_list(remote: Option<String>) -> Result<Vec<NeoForgeVersion>> {
+    let remote = match remote {
+        None => DEFAULT_MAVEN_URL.to_string(),
+        Some(remote) => remote,
+    };
+    let legacy = get_maven_versions(&remote, LEGACY_FORGE_ARTIFACT).await?;
+    let versions = get_maven_versions(&remote, NEOFORGE_ARTIFACT).await?;
+    Ok(legacy
+        .iter()
+        .filter_map(|version| NeoForgeVersion::from_legacy_forge(version))
+        .chain(
+            versions
+                .iter()
+                .filter_map(|version| NeoForgeVersion::from_neoforge(version)),
+        )
+        .collect())
+}
+
+pub async fn get_neoforge_version_list_from_mcversion(
+    remote: Option<String>,
+    mcversion: &str,
+) -> Result<Vec<NeoForgeVersion>> {
+    Ok(get_neoforge_version_list(remote)
+        .await?
+        .into_iter()
+        .filter(|version| version.mcversion == mcversion)
+        .collect())
+}
+
+async fn get_maven_versions(remote: &str, artifact: &str) -> Result<Vec<String>> {
+    let url = format!(
+        "{remote}/{}/maven-metadata.xml",
+        artifact.replace(['.', ':'], "/")
+    );
+    let response = source::get(&url).await?;
+    Ok(metadata_versions(&response.text().await?))
+}
@@ -49,7 +49,7 @@ pub async fn download_optifine_installer<P, D>(
     };
     download(Download {
         url,
-        file: dest_path,
+        file: AsRef::<Path>::as_ref(&dest_path).to_path_buf(),
         sha1: None,
     })
         .await?;
@@ -19,7 +19,9 @@
 pub mod install;
 pub mod version_list;
 
-const DEFAULT_META_URL: &str = "https://download.mcbbs.net/optifine";
+/// OptiFine has no official download api, BMCLAPI is used, and other BMCLAPI compatible
+/// sources replace it through [`crate::core::source`].
+const DEFAULT_META_URL: &str = "https://bmclapi2.bangbang93.com/optifine";
 
 // todo: 支持optifine安装选项
 
@@ -19,6 +19,8 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+use crate::core::source;
+
 use super::DEFAULT_META_URL;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,7 +43,7 @@ impl OptifineVersionList {
             Some(remote) => format!("{remote}/{mcversion}"),
             None => format!("{DEFAULT_META_URL}/{mcversion}"),
         };
-        Ok(reqwest::get(url)
+        Ok(source::get(&url)
             .await?
             .json::<OptifineVersionList>()
             .await?)
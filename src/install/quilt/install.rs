@@ -18,7 +18,7 @@
 
 use tokio::fs::{self, create_dir_all};
 
-use crate::core::{folder::MinecraftLocation, version::Version};
+use crate::core::{folder::MinecraftLocation, source, version::Version};
 
 use super::DEFAULT_META_URL;
 
@@ -31,7 +31,7 @@ pub async fn install_quilt_version_json(
     let remote = remote.unwrap_or(DEFAULT_META_URL.to_string());
     let url = format!("{remote}/v3/versions/loader/{mcversion}/{quilt_version}/profile/json");
 
-    let response = reqwest::get(url).await.unwrap();
+    let response = source::get(&url).await.unwrap();
 
     let quilt_version: Version = response.json().await.unwrap();
 
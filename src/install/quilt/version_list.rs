@@ -18,6 +18,8 @@
 
 use anyhow::Result;
 
+use crate::core::source;
+
 use super::{QuiltArtifactVersion, QuiltVersion, DEFAULT_META_URL};
 
 pub async fn get_quilt_version_list(remote: Option<String>) -> Result<Vec<QuiltArtifactVersion>> {
@@ -26,7 +28,7 @@ pub async fn get_quilt_version_list(remote: Option<String>) -> Result<Vec<QuiltA
         Some(remote) => remote,
     };
     let url = format!("{remote}/v3/versions/loader");
-    let response = reqwest::get(url).await?;
+    let response = source::get(&url).await?;
     Ok(response.json().await?)
 }
 
@@ -39,6 +41,6 @@ pub async fn get_quilt_version_list_from_mcversion(
         Some(remote) => remote,
     };
     let url = format!("{remote}/v3/versions/loader/{mcversion}");
-    let response = reqwest::get(url).await?;
+    let response = source::get(&url).await?;
     Ok(response.json().await?)
 }
@@ -0,0 +1,442 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Find the Java installations on this computer
+//!
+//! [`discover`] looks in `JAVA_HOME`, `PATH`, `/usr/lib/jvm`, SDKMAN and the Java runtimes
+//! installed by the official launcher or by this crate. Every installation is described by its
+//! `release` file, or by the properties printed by `java -XshowSettings:properties -version` if it
+//! has none. [`select_java`] picks the one to launch a version with, and
+//! [`runtime::install_java_runtime`] installs the Java runtime Mojang made for it.
+//!
+//! # Example
+//!
+//! ```
+//! use aml_core::core::folder::MinecraftLocation;
+//! use aml_core::java::discover;
+//!
+//! async fn fn_name() {
+//!     let minecraft = MinecraftLocation::new(".minecraft");
+//!     for java in discover(Some(&minecraft)).await {
+//!         println!("Java {} ({:?}) at {}", java.major_version, java.vendor, java.home.display());
+//!     }
+//! }
+//! ```
+//!
+//! Choose the Java for a version:
+//!
+//! ```
+//! use aml_core::core::{folder::MinecraftLocation, version::ResolvedVersion, JavaExec, PlatformInfo};
+//! use aml_core::java::{discover, select_java};
+//!
+//! async fn fn_name(version: ResolvedVersion, minecraft: MinecraftLocation) {
+//!     let installations = discover(Some(&minecraft)).await;
+//!     let platform = PlatformInfo::new().await;
+//!     match select_java(&version, &installations, &platform) {
+//!         Ok(java) => println!("{}", JavaExec::from(java.clone()).binary.display()),
+//!         Err(err) => println!("{err}"),
+//!     }
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use tokio::process::Command;
+
+use crate::core::{folder::MinecraftLocation, version::normalize_arch, JavaExec};
+
+pub mod runtime;
+pub mod select;
+
+pub use select::{select_java, JavaError, JavaRequirement};
+
+#[cfg(windows)]
+const JAVA_BINARY: &str = "java.exe";
+#[cfg(not(windows))]
+const JAVA_BINARY: &str = "java";
+
+/// Folders which contain Java installations, on Linux.
+const JVM_FOLDERS: &[&str] = &["/usr/lib/jvm", "/usr/lib64/jvm", "/usr/java", "/opt/java"];
+
+/// How deep the Java installations are searched in a folder, the official launcher keeps them in
+/// `runtime/<component>/<platform>/<component>`.
+const MAX_DEPTH: usize = 3;
+
+/// A Java installation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JavaInstallation {
+    /// The folder which contains `bin/java`.
+    pub home: PathBuf,
+    pub binary: PathBuf,
+
+    /// The full version, like `17.0.8` or `1.8.0_392`.
+    pub version: String,
+
+    /// The major version, `8` for `1.8.0_392`.
+    pub major_version: u32,
+    pub vendor: Option<String>,
+
+    /// The architecture, unified like `PlatformInfo::arch`, e.g. `x64` or `aarch64`.
+    pub arch: Option<String>,
+    pub is_jdk: bool,
+}
+
+impl From<JavaInstallation> for JavaExec {
+    fn from(java: JavaInstallation) -> Self {
+        JavaExec {
+            binary: java.binary,
+        }
+    }
+}
+
+impl JavaInstallation {
+    /// Describe the Java installation in `home`, from its `release` file if there is one,
+    /// otherwise by running it.
+    pub async fn from_home<P: AsRef<Path>>(home: P) -> Result<Self> {
+        let home = home.as_ref();
+        let binary = home.join("bin").join(JAVA_BINARY);
+        if !binary.is_file() {
+            return Err(anyhow!("{} is not a Java home", home.display()));
+        }
+        let properties = match tokio::fs::read_to_string(home.join("release")).await {
+            Ok(release) => parse_release(&release),
+            Err(_) => HashMap::new(),
+        };
+        if !properties.contains_key("JAVA_VERSION") {
+            return Self::probe(&binary).await;
+        }
+        let version = properties["JAVA_VERSION"].clone();
+        let is_jdk = match properties.get("IMAGE_TYPE") {
+            Some(image_type) => image_type == "JDK",
+            None => has_javac(home),
+        };
+        Ok(Self {
+            home: home.to_path_buf(),
+            binary,
+            major_version: parse_major_version(&version)
+                .ok_or(anyhow!("Bad Java version {version}"))?,
+            version,
+            vendor: properties.get("IMPLEMENTOR").cloned(),
+            arch: properties
+                .get("OS_ARCH")
+                .map(|arch| normalize_arch(arch).unwrap_or(arch).to_string()),
+            is_jdk,
+        })
+    }
+
+    /// Describe the Java installation by running `java -XshowSettings:properties -version`.
+    pub async fn probe<P: AsRef<Path>>(binary: P) -> Result<Self> {
+        let binary = binary.as_ref();
+        let output = Command::new(binary)
+            .args(["-XshowSettings:properties", "-version"])
+            .output()
+            .await?;
+        // the properties are printed to stderr
+        let properties = parse_properties(&String::from_utf8_lossy(&output.stderr));
+        let version = properties
+            .get("java.version")
+            .cloned()
+            .ok_or(anyhow!("{} printed no java.version", binary.display()))?;
+        let home = match properties.get("java.home") {
+            Some(home) => PathBuf::from(home),
+            None => binary
+                .parent()
+                .and_then(Path::parent)
+                .ok_or(anyhow!("{} is not in a Java home", binary.display()))?
+                .to_path_buf(),
+        };
+        // java 8 reports the jre folder inside the jdk
+        let is_jdk = has_javac(&home) || home.parent().is_some_and(has_javac);
+        Ok(Self {
+            binary: binary.to_path_buf(),
+            home,
+            major_version: parse_major_version(&version)
+                .ok_or(anyhow!("Bad Java version {version}"))?,
+            version,
+            vendor: properties.get("java.vendor").cloned(),
+            arch: properties
+                .get("os.arch")
+                .map(|arch| normalize_arch(arch).unwrap_or(arch).to_string()),
+            is_jdk,
+        })
+    }
+}
+
+/// The major version of a Java version string: `1.8.0_392` is 8, `17.0.8` is 17.
+pub fn parse_major_version(version: &str) -> Option<u32> {
+    let mut parts = version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|part| !part.is_empty());
+    match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok(),
+        major => Some(major),
+    }
+}
+
+fn has_javac(home: &Path) -> bool {
+    home.join("bin")
+        .join(if cfg!(windows) { "javac.exe" } else { "javac" })
+        .is_file()
+}
+
+/// `KEY="value"` lines of the `release` file.
+fn parse_release(release: &str) -> HashMap<String, String> {
+    release
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.trim().to_string(),
+                value.trim().trim_matches('"').to_string(),
+            )
+        })
+        .collect()
+}
+
+/// `key = value` lines printed by `-XshowSettings:properties`, the continuation lines of lists
+/// like `java.library.path` are skipped.
+fn parse_properties(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(" = "))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// The places to look for Java installations: Java homes, folders of Java homes, or `java`
+/// binaries from `PATH`.
+pub fn search_paths(minecraft: Option<&MinecraftLocation>) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(java_home) = std::env::var_os("JAVA_HOME") {
+        paths.push(PathBuf::from(java_home));
+    }
+    if let Some(path) = std::env::var_os("PATH") {
+        paths.extend(
+            std::env::split_paths(&path)
+                .map(|folder| folder.join(JAVA_BINARY))
+                .filter(|binary| binary.is_file()),
+        );
+    }
+    paths.extend(JVM_FOLDERS.iter().map(PathBuf::from));
+    if let Some(home) = std::env::var_os("HOME") {
+        let home = PathBuf::from(home);
+        paths.push(home.join(".sdkman/candidates/java"));
+        paths.push(home.join(".jdks"));
+    }
+    if let Some(minecraft) = minecraft {
+        paths.push(minecraft.root.join("runtime"));
+    }
+    paths
+}
+
+/// Find the Java installations in the standard locations, see [`search_paths`].
+pub async fn discover(minecraft: Option<&MinecraftLocation>) -> Vec<JavaInstallation> {
+    scan(search_paths(minecraft)).await
+}
+
+/// Find the Java installations in `paths`.
+///
+/// A path can be a Java home, a folder of Java homes (searched a few levels deep), or a `java`
+/// binary, which is resolved through symlinks like `/usr/bin/java`. Installations are only
+/// listed once, even if they are found in several paths.
+pub async fn scan<I, P>(paths: I) -> Vec<JavaInstallation>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let mut homes = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        if path.file_name() == Some(OsStr::new(JAVA_BINARY)) && path.is_file() {
+            if let Some(home) = std::fs::canonicalize(path)
+                .ok()
+                .and_then(|binary| Some(binary.parent()?.parent()?.to_path_buf()))
+            {
+                homes.push(home);
+            }
+        } else {
+            find_homes(path, MAX_DEPTH, &mut homes);
+        }
+    }
+
+    let mut installations: Vec<JavaInstallation> = Vec::new();
+    for home in homes {
+        let home = std::fs::canonicalize(&home).unwrap_or(home);
+        if installations.iter().any(|java| java.home == home) {
+            continue;
+        }
+        if let Ok(java) = JavaInstallation::from_home(&home).await {
+            installations.push(java);
+        }
+    }
+    installations
+}
+
+fn find_homes(folder: &Path, depth: usize, homes: &mut Vec<PathBuf>) {
+    if folder.join("bin").join(JAVA_BINARY).is_file() {
+        homes.push(folder.to_path_buf());
+        return;
+    }
+    // the Java home of a macOS bundle
+    if folder.join("Contents/Home/bin").join(JAVA_BINARY).is_file() {
+        homes.push(folder.join("Contents/Home"));
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return;
+    };
+    let mut children: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    children.sort();
+    for child in children {
+        find_homes(&child, depth - 1, homes);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fake_java(home: &Path, release: Option<&str>, jdk: bool) {
+        std::fs::create_dir_all(home.join("bin")).unwrap();
+        std::fs::write(home.join("bin").join(JAVA_BINARY), "").unwrap();
+        if jdk {
+            std::fs::write(home.join("bin/javac"), "").unwrap();
+        }
+        if let Some(release) = release {
+            std::fs::write(home.join("release"), release).unwrap();
+        }
+    }
+
+    #[test]
+    fn parse_java_versions() {
+        assert_eq!(parse_major_version("1.8.0_392"), Some(8));
+        assert_eq!(parse_major_version("17.0.8"), Some(17));
+        assert_eq!(parse_major_version("21"), Some(21));
+        assert_eq!(parse_major_version("22-ea"), Some(22));
+        assert_eq!(parse_major_version("abc"), None);
+    }
+
+    #[tokio::test]
+    async fn scan_fake_java_homes() {
+        let root = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let jvm = root.join("jvm");
+        fake_java(
+            &jvm.join("temurin-17-jdk-amd64"),
+            Some("IMPLEMENTOR=\"Eclipse Adoptium\"\nJAVA_VERSION=\"17.0.8\"\nOS_ARCH=\"amd64\"\nIMAGE_TYPE=\"JDK\"\n"),
+            false,
+        );
+        fake_java(
+            &jvm.join("java-8-openjdk/jre"),
+            Some("JAVA_VERSION=\"1.8.0_392\"\nOS_ARCH=\"aarch64\"\n"),
+            false,
+        );
+        fake_java(
+            &root.join("jdk-21"),
+            Some("JAVA_VERSION=\"21.0.1\"\n"),
+            true,
+        );
+        // the official launcher layout
+        fake_java(
+            &root.join(".minecraft/runtime/java-runtime-gamma/linux/java-runtime-gamma"),
+            Some("JAVA_VERSION=\"17.0.8\"\nIMAGE_TYPE=\"JRE\"\n"),
+            false,
+        );
+        std::fs::create_dir_all(jvm.join("not-java/bin")).unwrap();
+
+        let installations = scan([
+            jvm.clone(),
+            root.join("jdk-21"),
+            jvm.join("temurin-17-jdk-amd64"),
+            root.join(".minecraft/runtime"),
+        ])
+        .await;
+        let found: Vec<_> = installations
+            .iter()
+            .map(|java| {
+                (
+                    java.home
+                        .strip_prefix(std::fs::canonicalize(&root).unwrap())
+                        .unwrap(),
+                    java.major_version,
+                    java.arch.as_deref(),
+                    java.is_jdk,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    Path::new("jvm/java-8-openjdk/jre"),
+                    8,
+                    Some("aarch64"),
+                    false
+                ),
+                (Path::new("jvm/temurin-17-jdk-amd64"), 17, Some("x64"), true),
+                (Path::new("jdk-21"), 21, None, true),
+                (
+                    Path::new(".minecraft/runtime/java-runtime-gamma/linux/java-runtime-gamma"),
+                    17,
+                    None,
+                    false
+                ),
+            ]
+        );
+        assert_eq!(installations[1].vendor.as_deref(), Some("Eclipse Adoptium"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn probe_java_without_release() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let home = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fake_java(&home, None, true);
+        let binary = home.join("bin/java");
+        std::fs::write(
+            &binary,
+            format!(
+                "#!/bin/sh\ncat >&2 <<EOF\nProperty settings:\n    java.home = {}\n    java.library.path = /usr/lib\n        /lib\n    java.vendor = Azul Systems, Inc.\n    java.version = 11.0.20\n    os.arch = amd64\n\nopenjdk version \"11.0.20\" 2023-07-18 LTS\nEOF\n",
+                home.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let java = scan([&home]).await.pop().unwrap();
+        assert_eq!(java.version, "11.0.20");
+        assert_eq!(java.major_version, 11);
+        assert_eq!(java.vendor.as_deref(), Some("Azul Systems, Inc."));
+        assert_eq!(java.arch.as_deref(), Some("x64"));
+        assert!(java.is_jdk);
+        std::fs::remove_dir_all(&home).unwrap();
+    }
+}
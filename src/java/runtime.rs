@@ -0,0 +1,380 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Install the Java runtimes of the official launcher
+//!
+//! Mojang publishes a Java runtime for every `javaVersion.component` of the version.json, like
+//! `jre-legacy` or `java-runtime-gamma`. The index lists a manifest per platform and component,
+//! which lists every file of the runtime. Files are downloaded LZMA-compressed when available.
+//!
+//! # Example
+//!
+//! ```
+//! use aml_core::core::{folder::MinecraftLocation, PlatformInfo};
+//! use aml_core::java::runtime::install_java_runtime;
+//!
+//! async fn fn_name() {
+//!     let minecraft = MinecraftLocation::new(".minecraft");
+//!     let platform = PlatformInfo::new().await;
+//!     let java = install_java_runtime("java-runtime-gamma", &minecraft, &platform)
+//!         .await
+//!         .unwrap();
+//!     println!("{}", java.binary.display());
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{
+        folder::MinecraftLocation, source, version::normalize_arch, Download, JavaExec, OsType,
+        PlatformInfo,
+    },
+    utils::download::{check_file_sha1, file_sha1, Downloader},
+};
+
+use super::JAVA_BINARY;
+
+/// The index of all Java runtimes, by platform and component.
+pub const JAVA_RUNTIME_INDEX_URL: &str = "https://piston-meta.mojang.com/v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RuntimeDownload {
+    pub sha1: String,
+    pub size: u64,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JavaRuntimeVersion {
+    /// Like `17.0.8`.
+    pub name: String,
+    pub released: String,
+}
+
+/// A runtime in the index
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JavaRuntimeInfo {
+    pub manifest: RuntimeDownload,
+    pub version: JavaRuntimeVersion,
+}
+
+/// `all.json`, platform -> component -> runtimes.
+pub type JavaRuntimeIndex = HashMap<String, HashMap<String, Vec<JavaRuntimeInfo>>>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JavaRuntimeFileDownloads {
+    pub raw: RuntimeDownload,
+    pub lzma: Option<RuntimeDownload>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum JavaRuntimeFile {
+    File {
+        #[serde(default)]
+        executable: bool,
+        downloads: JavaRuntimeFileDownloads,
+    },
+    Directory,
+    Link {
+        /// Relative to the folder of the link.
+        target: String,
+    },
+}
+
+/// The files of a runtime, by their path relative to the runtime folder
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JavaRuntimeManifest {
+    pub files: HashMap<String, JavaRuntimeFile>,
+}
+
+/// The platform name used in the runtime index, `None` if Mojang has no runtime for it.
+pub fn runtime_platform(platform: &PlatformInfo) -> Option<&'static str> {
+    let arch = normalize_arch(&platform.arch)?;
+    match (&platform.os_type, arch) {
+        (OsType::Linux, "x64") => Some("linux"),
+        (OsType::Linux, "x86") => Some("linux-i386"),
+        (OsType::Osx, "x64") => Some("mac-os"),
+        (OsType::Osx, "aarch64") => Some("mac-os-arm64"),
+        (OsType::Windows, "x64") => Some("windows-x64"),
+        (OsType::Windows, "x86") => Some("windows-x86"),
+        (OsType::Windows, "aarch64") => Some("windows-arm64"),
+        _ => None,
+    }
+}
+
+/// Install the runtime `component` into `<root>/runtime/<component>`.
+///
+/// Files which are already there with the right sha1 are kept, so this also repairs a broken
+/// runtime.
+pub async fn install_java_runtime(
+    component: &str,
+    minecraft: &MinecraftLocation,
+    platform: &PlatformInfo,
+) -> Result<JavaExec> {
+    install_java_runtime_from(JAVA_RUNTIME_INDEX_URL, component, minecraft, platform).await
+}
+
+async fn install_java_runtime_from(
+    index_url: &str,
+    component: &str,
+    minecraft: &MinecraftLocation,
+    platform: &PlatformInfo,
+) -> Result<JavaExec> {
+    let platform_name = runtime_platform(platform).ok_or_else(|| {
+        anyhow!(
+            "No Java runtime available for {} {}",
+            platform.name,
+            platform.arch
+        )
+    })?;
+    let index: JavaRuntimeIndex = source::get(index_url).await?.json().await?;
+    let info = index
+        .get(platform_name)
+        .and_then(|components| components.get(component))
+        .and_then(|runtimes| runtimes.first())
+        .ok_or_else(|| anyhow!("No Java runtime {component} for {platform_name}"))?;
+    let manifest: JavaRuntimeManifest = source::get(&info.manifest.url).await?.json().await?;
+
+    let home = minecraft.get_runtime(component);
+    install_files(&manifest, &home).await?;
+
+    // the macOS runtimes are bundles
+    let bundle = home.join("jre.bundle/Contents/Home");
+    let java_home = if bundle.is_dir() { bundle } else { home };
+    Ok(JavaExec {
+        binary: java_home.join("bin").join(JAVA_BINARY),
+    })
+}
+
+async fn install_files(manifest: &JavaRuntimeManifest, home: &Path) -> Result<()> {
+    let mut downloads = Vec::new();
+    let mut compressed = Vec::new();
+    let mut executables = Vec::new();
+    let mut links = Vec::new();
+    for (name, file) in &manifest.files {
+        let path = home.join(relative_path(name)?);
+        match file {
+            JavaRuntimeFile::Directory => tokio::fs::create_dir_all(&path).await?,
+            JavaRuntimeFile::Link { target } => links.push((path, target)),
+            JavaRuntimeFile::File {
+                executable,
+                downloads: file_downloads,
+            } => {
+                if *executable {
+                    executables.push(path.clone());
+                }
+                if check_file_sha1(&path, &file_downloads.raw.sha1).await {
+                    continue;
+                }
+                match &file_downloads.lzma {
+                    Some(lzma) => {
+                        let mut lzma_path = path.clone().into_os_string();
+                        lzma_path.push(".lzma");
+                        let lzma_path = PathBuf::from(lzma_path);
+                        downloads.push(Download {
+                            url: lzma.url.clone(),
+                            file: lzma_path.clone(),
+                            sha1: Some(lzma.sha1.clone()),
+                        });
+                        compressed.push((lzma_path, path, file_downloads.raw.sha1.clone()));
+                    }
+                    None => downloads.push(Download {
+                        url: file_downloads.raw.url.clone(),
+                        file: path,
+                        sha1: Some(file_downloads.raw.sha1.clone()),
+                    }),
+                }
+            }
+        }
+    }
+    Downloader::default()
+        .download_all(downloads)
+        .await
+        .into_result()?;
+
+    for (lzma_path, path, sha1) in compressed {
+        decompress(&lzma_path, &path).await?;
+        let actual = file_sha1(&path).await?;
+        if !actual.eq_ignore_ascii_case(&sha1) {
+            tokio::fs::remove_file(&path).await?;
+            return Err(anyhow!(
+                "sha1 mismatch of {}: expected {sha1}, got {actual}",
+                path.display()
+            ));
+        }
+        tokio::fs::remove_file(&lzma_path).await?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        for path in executables {
+            tokio::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).await?;
+        }
+        for (path, target) in links {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            if tokio::fs::symlink_metadata(&path).await.is_ok() {
+                tokio::fs::remove_file(&path).await?;
+            }
+            tokio::fs::symlink(target, &path).await?;
+        }
+    }
+    // the Windows runtimes have no links and no executable bit
+    #[cfg(not(unix))]
+    let _ = (executables, links);
+    Ok(())
+}
+
+/// Only paths inside the runtime folder are accepted.
+fn relative_path(name: &str) -> Result<&Path> {
+    let path = Path::new(name);
+    if path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        Ok(path)
+    } else {
+        Err(anyhow!("Invalid path in the Java runtime manifest: {name}"))
+    }
+}
+
+async fn decompress(from: &Path, to: &Path) -> Result<()> {
+    let from = from.to_path_buf();
+    let to = to.to_path_buf();
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let mut input = std::io::BufReader::new(std::fs::File::open(&from)?);
+        let mut output = std::io::BufWriter::new(std::fs::File::create(&to)?);
+        lzma_rs::lzma_decompress(&mut input, &mut output)
+            .map_err(|err| anyhow!("Failed to decompress {}: {err}", from.display()))?;
+        Ok(())
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::utils::test_server::{serve_files, sha1, Files};
+
+    #[tokio::test]
+    async fn install_runtime_from_manifest() {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let platform = PlatformInfo {
+            arch: "x64".to_string(),
+            name: "linux".to_string(),
+            os_type: OsType::Linux,
+            version: String::new(),
+        };
+        let java = b"#!/bin/sh\necho java\n".to_vec();
+        let release = b"JAVA_VERSION=\"17.0.8\"\n".to_vec();
+        let mut release_lzma = Vec::new();
+        lzma_rs::lzma_compress(&mut release.as_slice(), &mut release_lzma).unwrap();
+
+        let files = Files::default();
+        let base = serve_files(files.clone()).await;
+        let download = |path: &str, data: &[u8]| json!({"sha1": sha1(data), "size": data.len(), "url": format!("{base}{path}")});
+        let manifest = json!({"files": {
+            "bin": {"type": "directory"},
+            "bin/java": {
+                "type": "file",
+                "executable": true,
+                "downloads": {"raw": download("/java", &java)}
+            },
+            "release": {
+                "type": "file",
+                "executable": false,
+                "downloads": {
+                    "raw": download("/release", &release),
+                    "lzma": download("/release.lzma", &release_lzma)
+                }
+            },
+            "legal/java.base/LICENSE": {"type": "link", "target": "../../release"}
+        }})
+        .to_string()
+        .into_bytes();
+        let index = json!({
+            "linux": {"java-runtime-gamma": [{
+                "availability": {"group": 1, "progress": 100},
+                "manifest": download("/manifest.json", &manifest),
+                "version": {"name": "17.0.8", "released": "2023-07-18T00:00:00+00:00"}
+            }]},
+            "windows-x64": {"java-runtime-gamma": []}
+        })
+        .to_string()
+        .into_bytes();
+        {
+            let mut files = files.lock().unwrap();
+            files.insert("/all.json".to_string(), index);
+            files.insert("/manifest.json".to_string(), manifest);
+            files.insert("/java".to_string(), java.clone());
+            // only the compressed variant is downloaded
+            files.insert("/release.lzma".to_string(), release_lzma);
+        }
+
+        let index_url = format!("{base}/all.json");
+        let exec =
+            install_java_runtime_from(&index_url, "java-runtime-gamma", &minecraft, &platform)
+                .await
+                .unwrap();
+        let home = minecraft.get_runtime("java-runtime-gamma");
+        assert_eq!(exec.binary, home.join("bin/java"));
+        assert_eq!(std::fs::read(&exec.binary).unwrap(), java);
+        assert_eq!(std::fs::read(home.join("release")).unwrap(), release);
+        assert!(!home.join("release.lzma").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&exec.binary)
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o755);
+            let license = home.join("legal/java.base/LICENSE");
+            assert_eq!(
+                std::fs::read_link(&license).unwrap(),
+                PathBuf::from("../../release")
+            );
+            assert_eq!(std::fs::read(&license).unwrap(), release);
+        }
+
+        // installed files are kept, even when they can't be downloaded any more
+        files.lock().unwrap().remove("/java");
+        install_java_runtime_from(&index_url, "java-runtime-gamma", &minecraft, &platform)
+            .await
+            .unwrap();
+
+        let error = install_java_runtime_from(&index_url, "jre-legacy", &minecraft, &platform)
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "No Java runtime jre-legacy for linux");
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
+    }
+}
@@ -0,0 +1,229 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Choose the Java to launch a version with
+
+use crate::core::{version::ResolvedVersion, PlatformInfo};
+
+use super::JavaInstallation;
+
+/// LaunchWrapper (Forge 1.12.2 and older, LiteLoader) casts the system class loader to
+/// `URLClassLoader`, which fails since Java 9.
+const LAUNCH_WRAPPER: &str = "net.minecraft.launchwrapper.Launch";
+
+/// ModLauncher, used by Forge since 1.13.
+const MOD_LAUNCHER: &str = "cpw.mods.modlauncher.Launcher";
+
+/// The Java versions a resolved version can run on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JavaRequirement {
+    /// `javaVersion.majorVersion` of the version.json.
+    pub recommended: u32,
+    pub min: u32,
+
+    /// The mod loader doesn't work on newer Java, like old Forge which needs exactly Java 8.
+    pub max: Option<u32>,
+
+    /// `javaVersion.component`, the Mojang runtime made for this version, like `java-runtime-gamma`.
+    pub component: String,
+}
+
+impl JavaRequirement {
+    pub fn from_version(version: &ResolvedVersion) -> Self {
+        let recommended = version.java_version.major_version.max(8) as u32;
+        let max = match version.main_class.as_str() {
+            LAUNCH_WRAPPER => Some(8),
+            // Forge for 1.13 - 1.16, these versions recommend Java 8
+            MOD_LAUNCHER if recommended == 8 => Some(8),
+            _ => None,
+        };
+        Self {
+            recommended,
+            min: recommended,
+            max,
+            component: version.java_version.component.clone(),
+        }
+    }
+
+    pub fn is_satisfied_by(&self, java: &JavaInstallation) -> bool {
+        java.major_version >= self.min && self.max.is_none_or(|max| java.major_version <= max)
+    }
+}
+
+/// Errors when choosing the Java
+#[derive(Debug, Clone, PartialEq)]
+pub enum JavaError {
+    /// None of the Java installations satisfies the requirement, `found` are their major versions.
+    NoCompatibleJava {
+        requirement: JavaRequirement,
+        found: Vec<u32>,
+    },
+}
+
+impl std::fmt::Display for JavaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JavaError::NoCompatibleJava { requirement, found } => {
+                match requirement.max {
+                    Some(max) if max == requirement.min => write!(f, "Java {max} is required")?,
+                    Some(max) => write!(f, "Java {} to {max} is required", requirement.min)?,
+                    None => write!(f, "Java {} or newer is required", requirement.min)?,
+                }
+                if found.is_empty() {
+                    write!(f, ", but no Java is found")
+                } else {
+                    let found: Vec<_> = found.iter().map(u32::to_string).collect();
+                    write!(f, ", but only Java {} is found", found.join(", "))
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for JavaError {}
+
+/// Pick the best Java for the version from `installations`.
+///
+/// Among the ones satisfying [`JavaRequirement::from_version`], the Java for the arch of the
+/// platform is preferred, then the recommended major version, then the oldest newer one. The
+/// Mojang runtime of the version's component wins a tie.
+pub fn select_java<'a>(
+    version: &ResolvedVersion,
+    installations: &'a [JavaInstallation],
+    platform: &PlatformInfo,
+) -> Result<&'a JavaInstallation, JavaError> {
+    let requirement = JavaRequirement::from_version(version);
+    installations
+        .iter()
+        .filter(|java| requirement.is_satisfied_by(java))
+        .min_by_key(|java| {
+            let other_arch = java
+                .arch
+                .as_ref()
+                .is_some_and(|arch| arch != &platform.arch);
+            let from_component = java
+                .home
+                .components()
+                .any(|component| component.as_os_str() == requirement.component.as_str());
+            (
+                other_arch,
+                java.major_version != requirement.recommended,
+                java.major_version,
+                !from_component,
+            )
+        })
+        .ok_or_else(|| JavaError::NoCompatibleJava {
+            requirement: requirement.clone(),
+            found: installations
+                .iter()
+                .map(|java| java.major_version)
+                .collect(),
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::core::{version::JavaVersion, OsType};
+
+    fn version(main_class: &str, component: &str, major_version: i32) -> ResolvedVersion {
+        ResolvedVersion {
+            id: "test".to_string(),
+            arguments: None,
+            main_class: main_class.to_string(),
+            asset_index: None,
+            assets: String::new(),
+            downloads: None,
+            libraries: vec![],
+            minimum_launcher_version: 0,
+            release_time: String::new(),
+            time: String::new(),
+            version_type: "release".to_string(),
+            logging: None,
+            java_version: JavaVersion {
+                component: component.to_string(),
+                major_version,
+            },
+            inheritances: vec![],
+            path_chain: vec![],
+        }
+    }
+
+    fn java(home: &str, major_version: u32, arch: &str) -> JavaInstallation {
+        JavaInstallation {
+            home: PathBuf::from(home),
+            binary: PathBuf::from(home).join("bin/java"),
+            version: major_version.to_string(),
+            major_version,
+            vendor: None,
+            arch: Some(arch.to_string()),
+            is_jdk: false,
+        }
+    }
+
+    #[test]
+    fn select_compatible_java() {
+        let platform = PlatformInfo {
+            arch: "x64".to_string(),
+            name: "linux".to_string(),
+            os_type: OsType::Linux,
+            version: String::new(),
+        };
+        let installations = vec![
+            java("/usr/lib/jvm/java-21", 21, "x64"),
+            java("/usr/lib/jvm/java-8-x86", 8, "x86"),
+            java("/usr/lib/jvm/java-8", 8, "x64"),
+            java("/usr/lib/jvm/java-17", 17, "x64"),
+            java("/root/.minecraft/runtime/java-runtime-gamma", 17, "x64"),
+        ];
+        let select = |version: &ResolvedVersion| {
+            select_java(version, &installations, &platform).map(|java| java.home.clone())
+        };
+
+        let vanilla = version("net.minecraft.client.main.Main", "java-runtime-gamma", 17);
+        assert_eq!(
+            select(&vanilla),
+            Ok(PathBuf::from("/root/.minecraft/runtime/java-runtime-gamma"))
+        );
+        let old_forge = version(LAUNCH_WRAPPER, "jre-legacy", 8);
+        assert_eq!(select(&old_forge), Ok(PathBuf::from("/usr/lib/jvm/java-8")));
+        let modern = version("net.minecraft.client.main.Main", "java-runtime-delta", 21);
+        assert_eq!(select(&modern), Ok(PathBuf::from("/usr/lib/jvm/java-21")));
+
+        // only newer Java than old forge supports
+        let error = select_java(&old_forge, &installations[3..], &platform).unwrap_err();
+        assert_eq!(
+            error,
+            JavaError::NoCompatibleJava {
+                requirement: JavaRequirement {
+                    recommended: 8,
+                    min: 8,
+                    max: Some(8),
+                    component: "jre-legacy".to_string(),
+                },
+                found: vec![17, 17],
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Java 8 is required, but only Java 17, 17 is found"
+        );
+    }
+}
@@ -16,31 +16,25 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{
-    collections::{HashMap, HashSet},
-    env,
-    path::PathBuf,
-};
+use std::collections::{HashMap, HashSet};
 
 use anyhow::Result;
 use regex::Regex;
-use tokio::{fs, process::Command};
-use zip::ZipArchive;
-
-use crate::{
-    core::{
-        folder::MinecraftLocation, version::ResolvedVersion, JavaExec, OsType, PlatformInfo,
-        DELIMITER,
-    },
-    utils::unzip::decompression_all,
+
+use crate::core::{
+    folder::MinecraftLocation, version::ResolvedVersion, OsType, PlatformInfo, DELIMITER,
 };
 
-use super::options::{LaunchOptions, ProcessPriority, UserType, GC};
+use super::{
+    natives::prepare_natives,
+    options::{LaunchOptions, QuickPlay, UserType, GC},
+};
 
 /// launch arguments for launch
 ///
-/// You can use `from_launch_options` to generate launch parameters and use `to_launch_command` to
-/// convert to shell commands
+/// You can use `from_launch_options` to generate launch parameters, then pass them to
+/// [`Launcher::spawn`](super::launcher::Launcher::spawn) or export a start script with
+/// [`script::launch_script`](super::script::launch_script)
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct LaunchArguments(Vec<String>);
 
@@ -69,7 +63,7 @@ impl LaunchArguments {
             "-Dminecraft.client.jar={version_jar}",
             version_jar = launch_options
                 .version_root
-                .join(&format!("{}.jar", &launch_options.version_id))
+                .join(format!("{}.jar", &launch_options.version_id))
                 .to_string_lossy()
         ));
 
@@ -139,6 +133,8 @@ impl LaunchArguments {
             }
         }
 
+        prepare_natives(&version, &minecraft, &launch_options.native_path).await?;
+
         let mut jvm_options: HashMap<&str, String> = HashMap::new();
         jvm_options.insert(
             "natives_directory",
@@ -146,26 +142,58 @@ impl LaunchArguments {
         );
         jvm_options.insert("launcher_name", launch_options.launcher_name.clone());
         jvm_options.insert("launcher_version", launch_options.launcher_version.clone());
+        jvm_options.insert(
+            "library_directory",
+            minecraft.libraries.to_string_lossy().to_string(),
+        );
+        jvm_options.insert("classpath_separator", DELIMITER.to_string());
+        jvm_options.insert("version_name", version.id.clone());
         jvm_options.insert(
             "classpath",
             resolve_classpath(
-                &launch_options,
                 &version,
                 &minecraft,
                 launch_options.extra_class_paths.clone(),
             ),
         );
 
-        let mut jvm_arguments = version.arguments.clone().unwrap().jvm;
+        let arguments = version.arguments.clone().ok_or(anyhow::anyhow!(
+            "arguments is not found! version.json is broken"
+        ))?;
+        let mut features = launch_options.features.clone();
+        features
+            .entry("is_demo_user".to_string())
+            .or_insert(launch_options.is_demo);
+        features
+            .entry("has_custom_resolution".to_string())
+            .or_insert(!launch_options.fullscreen);
+        let quick_play = &launch_options.quick_play;
+        for (feature, enabled) in [
+            ("has_quick_plays_support", quick_play.is_some()),
+            (
+                "is_quick_play_singleplayer",
+                matches!(quick_play, Some(QuickPlay::Singleplayer(_))),
+            ),
+            (
+                "is_quick_play_multiplayer",
+                matches!(quick_play, Some(QuickPlay::Multiplayer(_))),
+            ),
+            (
+                "is_quick_play_realms",
+                matches!(quick_play, Some(QuickPlay::Realms(_))),
+            ),
+        ] {
+            features.entry(feature.to_string()).or_insert(enabled);
+        }
+
+        let mut jvm_arguments = arguments.jvm(platform, &features);
         if let Some(logging) = version.logging {
             if let Some(client) = logging.get("client") {
                 let argument = &client.argument;
-                let file_path = minecraft.get_version_root(&version.id).join("log4j2.xml");
+                let file_path = minecraft.get_log4j2_configuration(&version.id);
                 if tokio::fs::try_exists(&file_path).await? {
-                    jvm_arguments.push(format!(
-                        "\"{}\"",
-                        argument.replace("${path}", &file_path.to_string_lossy().to_string())
-                    ));
+                    jvm_arguments
+                        .push(argument.replace("${path}", file_path.to_string_lossy().as_ref()));
                 }
             }
         }
@@ -179,7 +207,7 @@ impl LaunchArguments {
 
         command_arguments.push(version.main_class);
 
-        let mut game_options = HashMap::with_capacity(13);
+        let mut game_options = HashMap::with_capacity(18);
 
         let assets_dir = launch_options.resource_path.join("assets");
         game_options.insert(
@@ -228,16 +256,33 @@ impl LaunchArguments {
             match launch_options.user_type {
                 UserType::Mojang => "mojang".to_string(),
                 UserType::Legacy => "legacy".to_string(),
+                UserType::Msa => "msa".to_string(),
             },
         );
         game_options.insert("resolution_width", launch_options.width.to_string());
         game_options.insert("resolution_height", launch_options.height.to_string());
+        game_options.insert(
+            "quickPlayPath",
+            launch_options
+                .game_path
+                .join("quickPlay")
+                .join("log.json")
+                .to_string_lossy()
+                .to_string(),
+        );
+        let (singleplayer, multiplayer, realms) = match launch_options.quick_play {
+            Some(QuickPlay::Singleplayer(world)) => (world, String::new(), String::new()),
+            Some(QuickPlay::Multiplayer(server)) => (String::new(), server, String::new()),
+            Some(QuickPlay::Realms(realm)) => (String::new(), String::new(), realm),
+            None => (String::new(), String::new(), String::new()),
+        };
+        game_options.insert("quickPlaySingleplayer", singleplayer);
+        game_options.insert("quickPlayMultiplayer", multiplayer);
+        game_options.insert("quickPlayRealms", realms);
 
         command_arguments.extend(
-            version
-                .arguments
-                .unwrap()
-                .game
+            arguments
+                .game(platform, &features)
                 .iter()
                 .map(|arg| format(arg, game_options.clone())),
         );
@@ -251,10 +296,10 @@ impl LaunchArguments {
         if launch_options.fullscreen {
             command_arguments.push("--fullscreen".to_string());
         }
-        let no_width_arguments = None
-            == command_arguments
-                .iter()
-                .find(|v| v == &&"--width".to_string());
+        let no_width_arguments = command_arguments
+            .iter()
+            .find(|v| v == &&"--width".to_string())
+            .is_none();
         if no_width_arguments && !launch_options.fullscreen {
             command_arguments.extend(vec![
                 "--width".to_string(),
@@ -267,103 +312,19 @@ impl LaunchArguments {
         Ok(LaunchArguments(command_arguments))
     }
 
-    /// spawn a command instance, you can use this to launch the game
-    pub async fn to_async_command(
-        &self,
-        java_exec: JavaExec,
-        launch_options: LaunchOptions,
-        platform: &PlatformInfo,
-    ) -> Result<std::process::Command> {
-        let mut command = format!(
-            "cd {}\n",
-            launch_options.version_root.to_string_lossy().to_string()
-        );
-        match platform.os_type {
-            OsType::Windows => {}
-            _ => command.push_str("nice "),
-        };
-
-        if let OsType::Windows = platform.os_type {
-            command.push_str(" -c ");
-        }
-
-        if platform.os_type != OsType::Windows {
-            match launch_options.process_priority {
-                ProcessPriority::High => {
-                    command.push_str("-n 0 ");
-                }
-                ProcessPriority::AboveNormal => {
-                    command.push_str("-n 5 ");
-                }
-                ProcessPriority::Normal => (), // nothing to do
-                ProcessPriority::BelowNormal => {
-                    command.push_str("-n 15 ");
-                }
-                ProcessPriority::Low => {
-                    command.push_str("-n 19 ");
-                }
-            };
-        }
-        // todo(after java exec): add -Dfile.encoding=encoding.name() and other
-        let mut launch_command = java_exec.binary.to_string_lossy().to_string();
-        launch_command.push_str(" ");
-        launch_command.push_str(&self.0.clone().join(" "));
-        command.push_str(&launch_command);
-        match platform.os_type {
-            OsType::Windows => command.push_str(&format!(
-                "\ndel /F /Q {}\n",
-                launch_options.native_path.to_string_lossy()
-            )),
-            _ => command.push_str(&format!(
-                "\n rm -rf {}",
-                launch_options.native_path.to_string_lossy()
-            )),
-        }
-        let script_path = match platform.os_type {
-            OsType::Linux => launch_options.version_root.join(".cache").join("launch.sh"),
-            OsType::Osx => launch_options.version_root.join(".cache").join("launch.sh"),
-            OsType::Windows => launch_options
-                .version_root
-                .join(".cache")
-                .join("launch.bat"),
-        };
+    /// The arguments passed to java.
+    pub fn as_slice(&self) -> &[String] {
+        &self.0
+    }
+}
 
-        fs::create_dir_all(script_path.parent().unwrap()).await?;
-        fs::write(&script_path, command).await?;
-
-        let mut command = match platform.os_type {
-            OsType::Windows => {
-                let vars = env::vars().find(|v| v.0 == "PATH").unwrap();
-
-                let path_vars = vars.1.as_str().split(";").collect::<Vec<&str>>(); // todo: test it in windows
-                let powershell_folder = PathBuf::from(
-                    path_vars
-                        .into_iter()
-                        .find(|v| v.to_lowercase().contains("powershell"))
-                        .unwrap(),
-                );
-
-                std::process::Command::new(
-                    powershell_folder
-                        .join("powershell.exe")
-                        .to_string_lossy()
-                        .to_string(),
-                )
-            }
-            _ => {
-                let mut chmod = Command::new("chmod");
-                chmod.args(&["+x", script_path.to_string_lossy().to_string().as_ref()]);
-                chmod.status().await?;
-                std::process::Command::new("bash")
-            }
-        };
-        command.arg(script_path);
-        Ok(command)
+impl From<Vec<String>> for LaunchArguments {
+    fn from(arguments: Vec<String>) -> Self {
+        Self(arguments)
     }
 }
 
 fn resolve_classpath(
-    options: &LaunchOptions,
     version: &ResolvedVersion,
     minecraft: &MinecraftLocation,
     extra_class_paths: Option<Vec<String>>,
@@ -371,20 +332,7 @@ fn resolve_classpath(
     let mut classpath = version
         .libraries
         .iter()
-        .filter(|lib| {
-            if lib.is_native_library {
-                let path = minecraft.get_library_by_path(&lib.download_info.path);
-                let native_folder = options.native_path.clone();
-                println!("{:#?},{:#?}", path, native_folder);
-                if let Ok(file) = std::fs::File::open(path) {
-                    if let Ok(mut zip_archive) = ZipArchive::new(file) {
-                        decompression_all(&mut zip_archive, &native_folder).unwrap_or(());
-                    }
-                }
-            }
-            // true
-            !lib.is_native_library
-        })
+        .filter(|lib| !lib.is_native_library)
         .map(|lib| {
             minecraft
                 .get_library_by_path(lib.download_info.path.clone())
@@ -413,10 +361,97 @@ fn format(template: &str, args: HashMap<&str, String>) -> String {
     let regex = Regex::new(r"\$\{(.*?)}").unwrap();
 
     regex
-        .replace_all(&template, |caps: &regex::Captures| {
+        .replace_all(template, |caps: &regex::Captures| {
             let key = String::from(&caps[1]);
             let value = args.get(&caps[1]).unwrap_or(&key);
             value.to_string()
         })
         .to_string()
 }
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::version::Version;
+
+    #[tokio::test]
+    async fn quick_play_arguments() {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let version = json!({
+            "id": "1.20.1",
+            "mainClass": "net.minecraft.client.main.Main",
+            "assetIndex": {"id": "5", "sha1": "", "size": 1, "totalSize": 1, "url": "https://a/5.json"},
+            "downloads": {"client": {"sha1": "", "size": 1, "url": "https://a/client.jar"}},
+            "libraries": [],
+            "arguments": {
+                "game": [
+                    {"rules": [{"action": "allow", "features": {"has_quick_plays_support": true}}],
+                     "value": ["--quickPlayPath", "${quickPlayPath}"]},
+                    {"rules": [{"action": "allow", "features": {"is_quick_play_singleplayer": true}}],
+                     "value": ["--quickPlaySingleplayer", "${quickPlaySingleplayer}"]},
+                    {"rules": [{"action": "allow", "features": {"is_quick_play_multiplayer": true}}],
+                     "value": ["--quickPlayMultiplayer", "${quickPlayMultiplayer}"]},
+                ],
+                "jvm": [],
+            },
+        });
+        let version_json = minecraft.get_version_json("1.20.1");
+        std::fs::create_dir_all(version_json.parent().unwrap()).unwrap();
+        std::fs::create_dir_all(&minecraft.assets).unwrap();
+        std::fs::write(&version_json, version.to_string()).unwrap();
+        let platform = PlatformInfo {
+            arch: "x64".to_string(),
+            name: "linux".to_string(),
+            os_type: OsType::Linux,
+            version: String::new(),
+        };
+        let resolved = Version::from_value(version)
+            .unwrap()
+            .parse(&minecraft, &platform)
+            .await
+            .unwrap();
+        let mut options = LaunchOptions::new("1.20.1", &minecraft).await.unwrap();
+
+        let arguments =
+            LaunchArguments::from_launch_options(options.clone(), resolved.clone(), &platform)
+                .await
+                .unwrap();
+        assert!(!arguments
+            .as_slice()
+            .iter()
+            .any(|arg| arg.contains("quickPlay")));
+
+        options.set_feature("has_quick_plays_support", true);
+        options.set_quick_play(Some(QuickPlay::Singleplayer("New World".to_string())));
+        let arguments = LaunchArguments::from_launch_options(options, resolved, &platform)
+            .await
+            .unwrap();
+        let quick_play_path = minecraft
+            .get_version_root("1.20.1")
+            .join("quickPlay")
+            .join("log.json");
+        let quick_play: Vec<_> = arguments
+            .as_slice()
+            .iter()
+            .skip_while(|arg| *arg != "--quickPlayPath")
+            .take(4)
+            .cloned()
+            .collect();
+        assert_eq!(
+            quick_play,
+            [
+                "--quickPlayPath",
+                quick_play_path.to_string_lossy().as_ref(),
+                "--quickPlaySingleplayer",
+                "New World"
+            ]
+        );
+        assert!(!arguments
+            .as_slice()
+            .contains(&"--quickPlayMultiplayer".to_string()));
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
+    }
+}
@@ -0,0 +1,647 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Find and analyze crash reports
+//!
+//! When the game crashes, Minecraft writes `crash-reports/crash-<time>-client.txt`, and the JVM
+//! writes `hs_err_pid<pid>.log` if it crashed itself. [`find_crash_report`] looks for the newest
+//! one in the game directory, [`CrashReport::parse`] extracts the description, the exception chain
+//! and the mod list, and [`CrashReport::analyze`] matches it against known causes and points out
+//! the mods in the stack trace.
+//!
+//! # Example
+//!
+//! [`GameProcess::analyze_crash`](super::launcher::GameProcess::analyze_crash) does all of this
+//! for the launched game, or step by step:
+//!
+//! ```
+//! use aml_core::game_data::mods::parse_folder;
+//! use aml_core::launch::crash::{find_crash_report, CrashReport};
+//!
+//! async fn fn_name() {
+//!     if let Some(path) = find_crash_report(".minecraft", None).await.unwrap() {
+//!         let report = CrashReport::from_path(&path).await.unwrap();
+//!         let mods = parse_folder(".minecraft/mods").unwrap();
+//!         let analysis = report.analyze(&mods);
+//!         println!("{:?} {:?}", analysis.causes, analysis.suspects);
+//!     }
+//! }
+//! ```
+
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::game_data::mods::{parse_mod, ResolvedMod};
+
+/// Mod ids which are part of every stack trace, they are never suspected.
+const IGNORED_MOD_IDS: &[&str] = &[
+    "minecraft",
+    "java",
+    "forge",
+    "neoforge",
+    "fml",
+    "fabricloader",
+    "fabric",
+    "quilt_loader",
+    "mixin",
+    "mixinextras",
+];
+
+static CLASS_FILE_VERSION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"class file version (\d+)\.\d+").unwrap());
+
+/// Fabric: `Mod 'Create' (create) 0.5.1 requires any version of flywheel, which is missing!`
+/// and Forge: `Mod ID: 'flywheel', Requested by: 'create'`
+static MISSING_DEPENDENCY: Lazy<Vec<Regex>> = Lazy::new(|| {
+    vec![
+        Regex::new(r"\((?P<mod>[\w-]+)\) \S+ requires (?:any version|version \S+) of (?:mod )?'?(?P<dependency>[\w-]+)'?,? which is missing").unwrap(),
+        Regex::new(r"Mod ID: '(?P<dependency>[\w-]+)', Requested by: '(?P<mod>[\w-]+)'").unwrap(),
+    ]
+});
+
+/// `Mixin apply for mod create failed` or `Mixin [create.mixins.json:...] from mod create failed`
+static MIXIN_FAILURE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"Mixin (?:apply for mod (?P<mod>[\w-]+) failed|\[(?P<config>[^\]]+)\] from mod (?P<source>[\w-]+) failed)").unwrap()
+});
+
+const OUT_OF_MEMORY: &[&str] = &[
+    "java.lang.OutOfMemoryError",
+    "There is insufficient memory for the Java Runtime Environment",
+    "Out of Memory Error",
+];
+
+const OPENGL: &[&str] = &[
+    "Pixel format not accelerated",
+    "GLFW error 65542",
+    "GLFW error 65543",
+    "The driver does not appear to support OpenGL",
+    "Could not create context",
+    "No OpenGL context found in the current thread",
+    "atio6axx.dll",
+    "atioglxx.dll",
+    "ig9icd64.dll",
+    "ig7icd64.dll",
+    "nvoglv64.dll",
+];
+
+/// Where the crash report comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashReportKind {
+    /// `crash-reports/crash-*.txt` written by Minecraft
+    Minecraft,
+
+    /// `hs_err_pid*.log` written by the JVM
+    Jvm,
+}
+
+/// A mod in the `Mod List` (Forge) or `Fabric Mods` section of the crash report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrashReportMod {
+    pub id: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+
+    /// The file name of the mod, only Forge lists it.
+    pub file: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrashReport {
+    pub kind: CrashReportKind,
+    pub path: PathBuf,
+
+    /// The `Description:` line, or the error line of a JVM crash.
+    pub description: Option<String>,
+
+    /// The exception and its `Caused by:` exceptions, outermost first.
+    pub exceptions: Vec<String>,
+
+    /// The `at ...` frames of all exceptions.
+    pub stacktrace: Vec<String>,
+    pub mods: Vec<CrashReportMod>,
+
+    /// The whole report
+    pub content: String,
+}
+
+/// A known cause of a crash
+#[derive(Debug, Clone, PartialEq)]
+pub enum CrashCause {
+    /// A class is compiled for a newer java, the java version it needs is known from the class
+    /// file version.
+    WrongJavaVersion {
+        required: Option<u32>,
+    },
+
+    /// `mod_id` requires `dependency`, which is not installed.
+    MissingDependency {
+        mod_id: String,
+        dependency: String,
+    },
+
+    /// A mixin could not be applied.
+    MixinFailure {
+        mod_id: Option<String>,
+    },
+    OutOfMemory,
+
+    /// The graphics driver doesn't support the OpenGL version or crashed.
+    OpenGl,
+}
+
+/// A mod which is likely the reason of the crash
+#[derive(Debug, Clone)]
+pub struct SuspectedMod {
+    pub id: String,
+
+    /// The installed mod with the same id or file name, if it is found.
+    pub resolved: Option<ResolvedMod>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CrashAnalysis {
+    pub report: CrashReport,
+    pub causes: Vec<CrashCause>,
+    pub suspects: Vec<SuspectedMod>,
+}
+
+/// Parse the mods in the `mods` folder, the files which are not mods are skipped.
+pub(crate) async fn installed_mods(mods_folder: PathBuf) -> Vec<ResolvedMod> {
+    tokio::task::spawn_blocking(move || {
+        let Ok(entries) = std::fs::read_dir(mods_folder) else {
+            return vec![];
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .filter_map(|path| parse_mod(path).ok())
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Find the newest crash report in `game_path`, ignoring the ones older than `since`.
+pub async fn find_crash_report<P: AsRef<Path>>(
+    game_path: P,
+    since: Option<SystemTime>,
+) -> Result<Option<PathBuf>> {
+    let game_path = game_path.as_ref();
+    let mut candidates = Vec::new();
+    for (folder, prefix, suffix) in [
+        (game_path.join("crash-reports"), "crash-", ".txt"),
+        (game_path.to_path_buf(), "hs_err_pid", ".log"),
+    ] {
+        let mut entries = match tokio::fs::read_dir(&folder).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with(prefix) || !name.ends_with(suffix) {
+                continue;
+            }
+            let modified = entry.metadata().await?.modified()?;
+            if since.is_none_or(|since| modified >= since) {
+                candidates.push((modified, entry.path()));
+            }
+        }
+    }
+    Ok(candidates
+        .into_iter()
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path))
+}
+
+impl CrashReport {
+    pub async fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = String::from_utf8_lossy(&tokio::fs::read(&path).await?).to_string();
+        Ok(Self::parse(path.as_ref(), &content))
+    }
+
+    /// Parse the content of a crash report, the kind is known from the file name.
+    pub fn parse(path: &Path, content: &str) -> Self {
+        let is_jvm = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with("hs_err_pid"));
+        let mut report = Self {
+            kind: CrashReportKind::Minecraft,
+            path: path.to_path_buf(),
+            description: None,
+            exceptions: vec![],
+            stacktrace: vec![],
+            mods: vec![],
+            content: content.to_string(),
+        };
+        if is_jvm {
+            report.kind = CrashReportKind::Jvm;
+            // the first line after the header, like `#  EXCEPTION_ACCESS_VIOLATION (0xc0000005) at pc=...`
+            report.description = content
+                .lines()
+                .skip_while(|line| !line.starts_with("# A fatal error has been detected"))
+                .skip(1)
+                .map(|line| line.trim_start_matches('#').trim())
+                .find(|line| !line.is_empty())
+                .map(str::to_string);
+            return report;
+        }
+
+        let mut lines = content.lines().peekable();
+        while let Some(line) = lines.next() {
+            if let Some(description) = line.strip_prefix("Description: ") {
+                report.description = Some(description.trim().to_string());
+                // the stack trace follows the description after an empty line
+                while lines.peek().is_some_and(|line| line.trim().is_empty()) {
+                    lines.next();
+                }
+                for line in lines.by_ref() {
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    let trimmed = line.trim();
+                    if let Some(frame) = trimmed.strip_prefix("at ") {
+                        report.stacktrace.push(frame.to_string());
+                    } else if let Some(cause) = trimmed.strip_prefix("Caused by: ") {
+                        report.exceptions.push(cause.to_string());
+                    } else if !line.starts_with(char::is_whitespace) {
+                        report.exceptions.push(trimmed.to_string());
+                    }
+                }
+            } else if let Some(header) = ["Mod List:", "Fabric Mods:", "Quilt Mods:"]
+                .into_iter()
+                .find(|header| line.trim() == *header)
+            {
+                let indent = line.len() - line.trim_start().len();
+                while let Some(line) = lines.peek() {
+                    if line.trim().is_empty() || line.len() - line.trim_start().len() <= indent {
+                        break;
+                    }
+                    let line = lines.next().unwrap().trim();
+                    if let Some(mod_info) = parse_mod_line(header, line) {
+                        report.mods.push(mod_info);
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    /// Match the report against the known causes, and find the mods in the stack trace.
+    ///
+    /// `mods` are the installed mods, used to find the files of the suspected mods.
+    pub fn analyze(&self, mods: &[ResolvedMod]) -> CrashAnalysis {
+        let mut causes = Vec::new();
+        let content = &self.content;
+        if content.contains("UnsupportedClassVersionError")
+            || content.contains("compiled by a more recent version of the Java Runtime")
+        {
+            let required = CLASS_FILE_VERSION
+                .captures(content)
+                .and_then(|captures| captures[1].parse::<u32>().ok()?.checked_sub(44));
+            causes.push(CrashCause::WrongJavaVersion { required });
+        }
+        for regex in MISSING_DEPENDENCY.iter() {
+            for captures in regex.captures_iter(content) {
+                let cause = CrashCause::MissingDependency {
+                    mod_id: captures["mod"].to_string(),
+                    dependency: captures["dependency"].to_string(),
+                };
+                if !causes.contains(&cause) {
+                    causes.push(cause);
+                }
+            }
+        }
+        if let Some(captures) = MIXIN_FAILURE.captures(content) {
+            let mod_id = captures
+                .name("mod")
+                .or(captures.name("source"))
+                .map(|mod_id| mod_id.as_str().to_string());
+            causes.push(CrashCause::MixinFailure { mod_id });
+        } else if content.contains("MixinApplyError") || content.contains("MixinTransformerError") {
+            causes.push(CrashCause::MixinFailure { mod_id: None });
+        }
+        if OUT_OF_MEMORY.iter().any(|text| content.contains(text)) {
+            causes.push(CrashCause::OutOfMemory);
+        }
+        if OPENGL.iter().any(|text| content.contains(text)) {
+            causes.push(CrashCause::OpenGl);
+        }
+
+        let mut suspects: Vec<String> = Vec::new();
+        let mut suspect = |id: &str| {
+            if !IGNORED_MOD_IDS.contains(&id) && !suspects.iter().any(|suspect| suspect == id) {
+                suspects.push(id.to_string());
+            }
+        };
+        for cause in &causes {
+            match cause {
+                CrashCause::MissingDependency { mod_id, .. } => suspect(mod_id),
+                CrashCause::MixinFailure {
+                    mod_id: Some(mod_id),
+                } => suspect(mod_id),
+                _ => (),
+            }
+        }
+        let known_ids = self
+            .mods
+            .iter()
+            .map(|mod_info| mod_info.id.as_str())
+            .chain(mods.iter().filter_map(|mod_info| mod_info.id.as_deref()));
+        for id in known_ids {
+            if id.len() > 2 && self.stacktrace.iter().any(|frame| frame_has_mod(frame, id)) {
+                suspect(id);
+            }
+        }
+
+        let suspects = suspects
+            .into_iter()
+            .map(|id| {
+                let file = self
+                    .mods
+                    .iter()
+                    .find(|mod_info| mod_info.id == id)
+                    .and_then(|mod_info| mod_info.file.as_deref());
+                let resolved = mods
+                    .iter()
+                    .find(|mod_info| {
+                        mod_info.id.as_deref() == Some(&id)
+                            || file.is_some()
+                                && mod_info
+                                    .file
+                                    .as_ref()
+                                    .and_then(|path| path.file_name())
+                                    .map(|name| name.to_string_lossy())
+                                    .as_deref()
+                                    == file
+                    })
+                    .cloned();
+                SuspectedMod { id, resolved }
+            })
+            .collect();
+        CrashAnalysis {
+            report: self.clone(),
+            causes,
+            suspects,
+        }
+    }
+}
+
+/// A package segment (`com.simibubi.create.Foo`) or a mixin handler (`handler$zza000$create$tick`)
+/// named after the mod id.
+fn frame_has_mod(frame: &str, id: &str) -> bool {
+    let method = frame.split('(').next().unwrap_or(frame);
+    let id = id.replace('-', "_");
+    method.split('.').any(|segment| segment == id) || method.contains(&format!("${id}$"))
+}
+
+/// Forge: `create-1.20.1-0.5.1.jar |Create |create |0.5.1 |DONE |Manifest: NOSIGNATURE`
+///
+/// Fabric/Quilt: `create: Create 0.5.1`
+fn parse_mod_line(header: &str, line: &str) -> Option<CrashReportMod> {
+    if header == "Mod List:" {
+        let columns: Vec<_> = line.split('|').map(str::trim).collect();
+        if columns.len() < 4 || columns[2].is_empty() {
+            return None;
+        }
+        return Some(CrashReportMod {
+            id: columns[2].to_string(),
+            name: Some(columns[1].to_string()),
+            version: Some(columns[3].to_string()),
+            file: Some(columns[0].to_string()),
+        });
+    }
+    let (id, rest) = line.split_once(": ")?;
+    let (name, version) = match rest.rsplit_once(' ') {
+        Some((name, version)) => (Some(name.to_string()), Some(version.to_string())),
+        None => (Some(rest.to_string()), None),
+    };
+    Some(CrashReportMod {
+        id: id.to_string(),
+        name,
+        version,
+        file: None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::game_data::mods::ResolvedDepends;
+
+    const FORGE_REPORT: &str = "---- Minecraft Crash Report ----
+// Why did you do that?
+
+Time: 2023-08-14 12:34:56
+Description: Ticking entity
+
+java.lang.RuntimeException: Ticking entity
+\tat net.minecraft.server.level.ServerLevel.tickNonPassenger(ServerLevel.java:693)
+\tat com.simibubi.create.content.trains.Carriage.tick(Carriage.java:120)
+Caused by: java.lang.NullPointerException: Cannot invoke \"Object.hashCode()\"
+\tat net.minecraft.world.entity.Entity.handler$zza000$jei$tick(Entity.java:12)
+\t... 12 more
+
+
+A detailed walkthrough of the error, its code path and all known details is as follows:
+---------------------------------------------------------------------------------------
+
+-- System Details --
+Details:
+\tMinecraft Version: 1.20.1
+\tMod List:
+\t\tcreate-1.20.1-0.5.1.f.jar                         |Create                        |create                        |0.5.1.f             |DONE      |Manifest: NOSIGNATURE
+\t\tjei-1.20.1-forge-15.2.0.27.jar                    |Just Enough Items             |jei                           |15.2.0.27           |DONE      |Manifest: NOSIGNATURE
+\t\tforge-1.20.1-47.1.0-universal.jar                 |Forge                         |forge                         |47.1.0              |DONE      |Manifest: NOSIGNATURE
+\tCrash Report UUID: 1c3a
+";
+
+    fn resolved_mod(id: &str, file: &str) -> ResolvedMod {
+        ResolvedMod {
+            id: Some(id.to_string()),
+            name: id.to_string(),
+            description: None,
+            version: None,
+            depends: ResolvedDepends {
+                minecraft: None,
+                java: None,
+                mod_loader: None,
+            },
+            authors: vec![],
+            license: None,
+            icon: None,
+            file: Some(PathBuf::from(file)),
+        }
+    }
+
+    #[test]
+    fn analyze_minecraft_crash_report() {
+        let report = CrashReport::parse(
+            Path::new("crash-reports/crash-2023-08-14_12.34.56-server.txt"),
+            FORGE_REPORT,
+        );
+        assert_eq!(report.kind, CrashReportKind::Minecraft);
+        assert_eq!(report.description.as_deref(), Some("Ticking entity"));
+        assert_eq!(
+            report.exceptions,
+            vec![
+                "java.lang.RuntimeException: Ticking entity",
+                "java.lang.NullPointerException: Cannot invoke \"Object.hashCode()\"",
+            ]
+        );
+        assert_eq!(report.stacktrace.len(), 3);
+        assert_eq!(report.mods.len(), 3);
+        assert_eq!(
+            report.mods[0],
+            CrashReportMod {
+                id: "create".to_string(),
+                name: Some("Create".to_string()),
+                version: Some("0.5.1.f".to_string()),
+                file: Some("create-1.20.1-0.5.1.f.jar".to_string()),
+            }
+        );
+
+        let mods = vec![
+            resolved_mod("create", "mods/create-1.20.1-0.5.1.f.jar"),
+            resolved_mod("jei", "mods/jei.jar"),
+        ];
+        let analysis = report.analyze(&mods);
+        assert!(analysis.causes.is_empty());
+        let suspects: Vec<_> = analysis
+            .suspects
+            .iter()
+            .map(|suspect| {
+                (
+                    suspect.id.as_str(),
+                    suspect.resolved.as_ref().and_then(|m| m.file.clone()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            suspects,
+            vec![
+                (
+                    "create",
+                    Some(PathBuf::from("mods/create-1.20.1-0.5.1.f.jar"))
+                ),
+                ("jei", Some(PathBuf::from("mods/jei.jar"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn match_known_causes() {
+        let causes = |content: &str| {
+            CrashReport::parse(Path::new("crash-1.txt"), content)
+                .analyze(&[])
+                .causes
+        };
+        assert_eq!(
+            causes("java.lang.UnsupportedClassVersionError: a/B has been compiled by a more recent version of the Java Runtime (class file version 61.0), this version of the Java Runtime only recognizes class file versions up to 52.0"),
+            vec![CrashCause::WrongJavaVersion { required: Some(17) }]
+        );
+        assert_eq!(
+            causes("java.lang.UnsupportedClassVersionError: a/B (class file version 12.0)"),
+            vec![CrashCause::WrongJavaVersion { required: None }]
+        );
+        assert_eq!(
+            causes("net.fabricmc.loader.impl.FormattedException: Mod resolution encountered an incompatible mod set!\n - Mod 'Create' (create) 0.5.1 requires any version of flywheel, which is missing!"),
+            vec![CrashCause::MissingDependency {
+                mod_id: "create".to_string(),
+                dependency: "flywheel".to_string()
+            }]
+        );
+        assert_eq!(
+            causes("Missing or unsupported mandatory dependencies:\n\tMod ID: 'flywheel', Requested by: 'create', Expected range: '[0.6.9,0.6.10)', Actual version: '[MISSING]'"),
+            vec![CrashCause::MissingDependency {
+                mod_id: "create".to_string(),
+                dependency: "flywheel".to_string()
+            }]
+        );
+        assert_eq!(
+            causes("org.spongepowered.asm.mixin.transformer.throwables.MixinTransformerError: An unexpected critical error was encountered\nCaused by: org.spongepowered.asm.mixin.throwables.MixinApplyError: Mixin [sodium.mixins.json:core.MixinWindow] from mod sodium failed injection check"),
+            vec![CrashCause::MixinFailure {
+                mod_id: Some("sodium".to_string())
+            }]
+        );
+        assert_eq!(
+            causes("org.spongepowered.asm.mixin.transformer.throwables.MixinTransformerError: An unexpected critical error was encountered"),
+            vec![CrashCause::MixinFailure { mod_id: None }]
+        );
+        assert_eq!(
+            causes("java.lang.OutOfMemoryError: Java heap space"),
+            vec![CrashCause::OutOfMemory]
+        );
+
+        let jvm = CrashReport::parse(
+            Path::new("hs_err_pid1234.log"),
+            "#\n# A fatal error has been detected by the Java Runtime Environment:\n#\n#  EXCEPTION_ACCESS_VIOLATION (0xc0000005) at pc=0x00007ffb, pid=1234\n#\n# Problematic frame:\n# C  [atio6axx.dll+0x1c4d5]\n",
+        );
+        assert_eq!(jvm.kind, CrashReportKind::Jvm);
+        assert_eq!(
+            jvm.description.as_deref(),
+            Some("EXCEPTION_ACCESS_VIOLATION (0xc0000005) at pc=0x00007ffb, pid=1234")
+        );
+        assert_eq!(jvm.analyze(&[]).causes, vec![CrashCause::OpenGl]);
+    }
+
+    #[tokio::test]
+    async fn find_newest_crash_report() {
+        let game_path = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        assert!(find_crash_report(&game_path, None).await.unwrap().is_none());
+        std::fs::create_dir_all(game_path.join("crash-reports")).unwrap();
+        let old = game_path.join("crash-reports/crash-2023-08-14_12.00.00-client.txt");
+        std::fs::write(&old, "old").unwrap();
+        let since = SystemTime::now();
+        let set_modified = |path: &Path, modified: SystemTime| {
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+        set_modified(&old, since - Duration::from_secs(60));
+        assert!(find_crash_report(&game_path, Some(since))
+            .await
+            .unwrap()
+            .is_none());
+        let jvm = game_path.join("hs_err_pid1234.log");
+        std::fs::write(&jvm, "jvm").unwrap();
+        set_modified(&jvm, since + Duration::from_secs(60));
+        std::fs::write(game_path.join("latest.log"), "log").unwrap();
+        assert_eq!(
+            find_crash_report(&game_path, Some(since)).await.unwrap(),
+            Some(jvm)
+        );
+        assert_eq!(
+            find_crash_report(&game_path, None).await.unwrap(),
+            find_crash_report(&game_path, Some(since)).await.unwrap()
+        );
+        std::fs::remove_dir_all(&game_path).unwrap();
+    }
+}
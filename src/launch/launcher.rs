@@ -0,0 +1,397 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Spawn and supervise the game process
+//!
+//! [`Launcher`] starts Java directly, without a shell, and returns a [`GameProcess`] to wait for
+//! or kill the game. The listener registered with [`Launcher::on_event`] receives the
+//! [`LaunchEvent`]s of the game process.
+
+use std::{
+    path::PathBuf,
+    process::{ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::SystemTime,
+};
+
+use anyhow::{anyhow, Result};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::Command,
+    sync::{watch, Notify},
+};
+
+use crate::core::{JavaExec, PlatformInfo};
+
+use super::{
+    argument::LaunchArguments,
+    crash::{find_crash_report, installed_mods, CrashAnalysis, CrashReport},
+    log::{log_channel, LogParser, LogSender, LogStream},
+    options::{LaunchOptions, ProcessPriority},
+};
+
+/// Lines in the game output which are printed once the game window has been created.
+const WINDOW_SHOWN_MARKERS: &[&str] = &[
+    "LWJGL Version: ",
+    "Backend library: LWJGL version",
+    "Created: 1024x512 textures-atlas",
+    "Sound engine started",
+];
+
+/// How many log records are kept until they are received, the oldest records are dropped when
+/// nobody receives them.
+const LOG_BUFFER: usize = 4096;
+
+/// Lifecycle events of the game process
+#[derive(Debug, Clone, PartialEq)]
+pub enum LaunchEvent {
+    /// The launch arguments are resolved and the process is about to be spawned.
+    Starting,
+
+    /// The game printed something that only happens after its window is created.
+    WindowShown,
+
+    /// The game exited normally, or was killed with [`GameProcess::kill`].
+    Exited(ExitStatus),
+
+    /// The game exited with a failure status.
+    Crashed(ExitStatus),
+}
+
+/// Launch the game
+///
+/// Use `Launcher::from_options()` to create a new instance, and `Launcher::on_event()` to listen
+/// to the lifecycle of the game process.
+pub struct Launcher {
+    options: LaunchOptions,
+    java: JavaExec,
+    on_event: Arc<dyn Fn(LaunchEvent) + Send + Sync>,
+}
+
+impl Launcher {
+    pub fn from_options(options: LaunchOptions, java: JavaExec) -> Self {
+        Self {
+            options,
+            java,
+            on_event: Arc::new(|_| {}),
+        }
+    }
+
+    /// Register the event listener, it is called from the tasks supervising the game process.
+    pub fn on_event(self, on_event: Box<dyn Fn(LaunchEvent) + Send + Sync>) -> Self {
+        Self {
+            on_event: Arc::from(on_event),
+            ..self
+        }
+    }
+
+    /// Resolve the version, prepare the natives and spawn the game.
+    pub async fn launch(&self) -> Result<GameProcess> {
+        let platform = PlatformInfo::new().await;
+        let version = self
+            .options
+            .version
+            .parse(&self.options.minecraft_location, &platform)
+            .await?;
+        let arguments =
+            LaunchArguments::from_launch_options(self.options.clone(), version, &platform).await?;
+        self.spawn(&arguments).await
+    }
+
+    /// Spawn java with the arguments, in the game directory.
+    pub async fn spawn(&self, arguments: &LaunchArguments) -> Result<GameProcess> {
+        (self.on_event)(LaunchEvent::Starting);
+
+        tokio::fs::create_dir_all(&self.options.game_path).await?;
+        let started = SystemTime::now();
+        let mut command = self.command();
+        command
+            .args(arguments.as_slice())
+            .current_dir(&self.options.game_path)
+            .envs(&self.options.env)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = command.spawn().map_err(|err| {
+            anyhow!(
+                "Failed to start {}: {err}",
+                self.java.binary.to_string_lossy()
+            )
+        })?;
+        let pid = child.id();
+
+        let window_shown = Arc::new(AtomicBool::new(false));
+        let (log_sender, logs) = log_channel(LOG_BUFFER);
+        let mut readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(watch_output(
+                stdout,
+                window_shown.clone(),
+                self.on_event.clone(),
+                log_sender.clone(),
+            ));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(watch_output(
+                stderr,
+                window_shown.clone(),
+                self.on_event.clone(),
+                log_sender.clone(),
+            ));
+        }
+
+        // the stream ends once both readers are done
+        drop(log_sender);
+
+        let kill = Arc::new(Notify::new());
+        let (status_sender, status) = watch::channel(None);
+        let on_event = self.on_event.clone();
+        let kill_requested = kill.clone();
+        tokio::spawn(async move {
+            let mut killed = false;
+            let status = tokio::select! {
+                status = child.wait() => status,
+                _ = kill_requested.notified() => {
+                    killed = true;
+                    match child.kill().await {
+                        Ok(()) => child.wait().await,
+                        Err(err) => Err(err),
+                    }
+                }
+            };
+            for reader in readers {
+                let _ = reader.await;
+            }
+            if let Ok(status) = &status {
+                if status.success() || killed {
+                    on_event(LaunchEvent::Exited(*status));
+                } else {
+                    on_event(LaunchEvent::Crashed(*status));
+                }
+            }
+            let _ = status_sender.send(Some(status.map_err(|err| err.to_string())));
+        });
+
+        Ok(GameProcess {
+            pid,
+            kill,
+            status,
+            logs: Some(logs),
+            game_path: self.options.game_path.clone(),
+            started,
+        })
+    }
+
+    fn command(&self) -> Command {
+        #[cfg(windows)]
+        {
+            // the priority classes of `SetPriorityClass`
+            let class = match self.options.process_priority {
+                ProcessPriority::High => 0x0000_0080,
+                ProcessPriority::AboveNormal => 0x0000_8000,
+                ProcessPriority::Normal => 0x0000_0020,
+                ProcessPriority::BelowNormal => 0x0000_4000,
+                ProcessPriority::Low => 0x0000_0040,
+            };
+            let mut command = Command::new(&self.java.binary);
+            command.creation_flags(class);
+            command
+        }
+        #[cfg(not(windows))]
+        {
+            // raising the priority needs privileges, so only lowering is supported
+            let niceness = match self.options.process_priority {
+                ProcessPriority::High | ProcessPriority::AboveNormal | ProcessPriority::Normal => {
+                    return Command::new(&self.java.binary)
+                }
+                ProcessPriority::BelowNormal => "5",
+                ProcessPriority::Low => "10",
+            };
+            let mut command = Command::new("nice");
+            command.args(["-n", niceness]).arg(&self.java.binary);
+            command
+        }
+    }
+}
+
+fn watch_output<R: AsyncRead + Unpin + Send + 'static>(
+    output: R,
+    window_shown: Arc<AtomicBool>,
+    on_event: Arc<dyn Fn(LaunchEvent) + Send + Sync>,
+    logs: LogSender,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(output).lines();
+        let mut parser = LogParser::default();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if WINDOW_SHOWN_MARKERS
+                .iter()
+                .any(|marker| line.contains(marker))
+                && !window_shown.swap(true, Ordering::SeqCst)
+            {
+                on_event(LaunchEvent::WindowShown);
+            }
+            if let Some(record) = parser.push_line(&line) {
+                logs.send(record);
+            }
+        }
+    })
+}
+
+/// The running game, returned by [`Launcher::spawn`]
+pub struct GameProcess {
+    pid: Option<u32>,
+    kill: Arc<Notify>,
+    status: watch::Receiver<Option<Result<ExitStatus, String>>>,
+    logs: Option<LogStream>,
+    game_path: PathBuf,
+    started: SystemTime,
+}
+
+impl GameProcess {
+    /// The process id, `None` if the process already exited when it was spawned.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// Take the log records of the game output, parsed from the log4j2 XML events or plain text.
+    ///
+    /// Up to 4096 records are kept until they are received, the oldest ones are dropped while the
+    /// buffer is full. Only the first call returns the stream.
+    pub fn take_logs(&mut self) -> Option<LogStream> {
+        self.logs.take()
+    }
+
+    /// The exit status if the game has exited.
+    pub fn try_status(&self) -> Option<Result<ExitStatus>> {
+        self.status
+            .borrow()
+            .as_ref()
+            .map(|status| status.clone().map_err(|err| anyhow!(err)))
+    }
+
+    /// Wait until the game exits.
+    pub async fn wait(&mut self) -> Result<ExitStatus> {
+        let status = self.status.wait_for(Option::is_some).await?;
+        match status.as_ref().unwrap() {
+            Ok(status) => Ok(*status),
+            Err(err) => Err(anyhow!("Failed to wait for the game: {err}")),
+        }
+    }
+
+    /// Find the crash report written since the game started, and analyze it with the mods in the
+    /// `mods` folder of the game directory.
+    ///
+    /// `None` if there is no crash report, like when the game was killed.
+    pub async fn analyze_crash(&self) -> Result<Option<CrashAnalysis>> {
+        let Some(path) = find_crash_report(&self.game_path, Some(self.started)).await? else {
+            return Ok(None);
+        };
+        let report = CrashReport::from_path(path).await?;
+        let mods = installed_mods(self.game_path.join("mods")).await;
+        Ok(Some(report.analyze(&mods)))
+    }
+
+    /// Kill the game and wait until it exits.
+    pub async fn kill(&mut self) -> Result<ExitStatus> {
+        self.kill.notify_one();
+        self.wait().await
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{core::folder::MinecraftLocation, launch::log::LogLevel};
+
+    async fn launcher(events: Arc<Mutex<Vec<LaunchEvent>>>) -> (Launcher, MinecraftLocation) {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let version_json = minecraft.get_version_json("1.20.1");
+        std::fs::create_dir_all(version_json.parent().unwrap()).unwrap();
+        std::fs::write(&version_json, r#"{"id": "1.20.1"}"#).unwrap();
+        let options = LaunchOptions::new("1.20.1", &minecraft).await.unwrap();
+        let java = JavaExec {
+            binary: "sh".into(),
+        };
+        let launcher = Launcher::from_options(options, java).on_event(Box::new(move |event| {
+            events.lock().unwrap().push(event);
+        }));
+        (launcher, minecraft)
+    }
+
+    fn script(script: &str) -> LaunchArguments {
+        LaunchArguments::from(vec!["-c".to_string(), script.to_string()])
+    }
+
+    #[tokio::test]
+    async fn supervise_game_process() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let (launcher, minecraft) = launcher(events.clone()).await;
+
+        let mut game = launcher
+            .spawn(&script(
+                "touch launched; echo '[12:34:56] [Render thread/INFO]: LWJGL Version: 3.3.1'; exit 3",
+            ))
+            .await
+            .unwrap();
+        assert!(game.pid().is_some());
+        let mut logs = game.take_logs().unwrap();
+        assert!(game.take_logs().is_none());
+        let status = game.wait().await.unwrap();
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                LaunchEvent::Starting,
+                LaunchEvent::WindowShown,
+                LaunchEvent::Crashed(status)
+            ]
+        );
+        let record = logs.recv().await.unwrap();
+        assert_eq!(record.level, LogLevel::Info);
+        assert_eq!(record.thread.as_deref(), Some("Render thread"));
+        assert_eq!(record.message, "LWJGL Version: 3.3.1");
+        assert!(logs.recv().await.is_none());
+
+        // no crash report
+        assert!(game.analyze_crash().await.unwrap().is_none());
+
+        // launched in the game directory
+        assert!(minecraft
+            .get_version_root("1.20.1")
+            .join("launched")
+            .is_file());
+
+        events.lock().unwrap().clear();
+        let mut game = launcher.spawn(&script("sleep 30")).await.unwrap();
+        let status = game.kill().await.unwrap();
+        assert!(!status.success());
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![LaunchEvent::Starting, LaunchEvent::Exited(status)]
+        );
+        assert!(game.try_status().unwrap().is_ok());
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
+    }
+}
@@ -0,0 +1,370 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Parse the game output into log records
+//!
+//! With the `logging.client` argument of the version.json, the game prints log4j2 XML events
+//! (`<log4j:Event ...>`) instead of plain text. [`LogParser`] accepts both, line by line, so the
+//! output of any version can be shown with levels and threads.
+
+use std::{
+    collections::VecDeque,
+    future::poll_fn,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use futures::Stream;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// `<log4j:Event logger="..." timestamp="..." level="..." thread="...">`
+static EVENT_ATTRIBUTE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(\w+)="([^"]*)""#).unwrap());
+
+/// `[12:34:56] [Render thread/INFO]: message`, or with the logger of forge
+/// `[12:34:56] [main/INFO] [net.minecraftforge.fml.loading/]: message`
+static PLAIN_TEXT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[([^\]]+)\] \[([^\]]+)/(FATAL|ERROR|WARN|INFO|DEBUG|TRACE)\](?: \[([^\]]*?)/?\])?: (.*)$")
+        .unwrap()
+});
+
+static CDATA: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<!\[CDATA\[(.*?)\]\]>").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Fatal,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+
+    /// Not printed by log4j, like the stack trace of an uncaught exception.
+    Unknown,
+}
+
+impl LogLevel {
+    fn parse(level: &str) -> Self {
+        match level.to_uppercase().as_str() {
+            "FATAL" => Self::Fatal,
+            "ERROR" => Self::Error,
+            "WARN" => Self::Warn,
+            "INFO" => Self::Info,
+            "DEBUG" => Self::Debug,
+            "TRACE" => Self::Trace,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// A line or an event of the game log
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    /// Milliseconds since the unix epoch for XML events, the plain text has no date, so it is
+    /// `None` there.
+    pub timestamp: Option<u64>,
+
+    /// The time as printed in plain text, like `12:34:56`.
+    pub time: Option<String>,
+    pub level: LogLevel,
+    pub thread: Option<String>,
+    pub logger: Option<String>,
+    pub message: String,
+
+    /// The stack trace attached to the event.
+    pub throwable: Option<String>,
+}
+
+impl LogRecord {
+    fn plain(line: &str) -> Self {
+        match PLAIN_TEXT.captures(line) {
+            Some(captures) => Self {
+                timestamp: None,
+                time: Some(captures[1].to_string()),
+                level: LogLevel::parse(&captures[3]),
+                thread: Some(captures[2].to_string()),
+                logger: captures
+                    .get(4)
+                    .map(|logger| logger.as_str().to_string())
+                    .filter(|logger| !logger.is_empty()),
+                message: captures[5].to_string(),
+                throwable: None,
+            },
+            None => Self {
+                timestamp: None,
+                time: None,
+                level: LogLevel::Unknown,
+                thread: None,
+                logger: None,
+                message: line.to_string(),
+                throwable: None,
+            },
+        }
+    }
+
+    fn xml(event: &str) -> Self {
+        let start = &event[..event.find('>').unwrap_or(event.len())];
+        let mut record = Self {
+            timestamp: None,
+            time: None,
+            level: LogLevel::Unknown,
+            thread: None,
+            logger: None,
+            message: String::new(),
+            throwable: None,
+        };
+        for captures in EVENT_ATTRIBUTE.captures_iter(start) {
+            let value = unescape(&captures[2]);
+            match &captures[1] {
+                "timestamp" => record.timestamp = value.parse().ok(),
+                "level" => record.level = LogLevel::parse(&value),
+                "thread" => record.thread = Some(value),
+                "logger" => record.logger = Some(value),
+                _ => (),
+            }
+        }
+        record.message = element_text(event, "log4j:Message").unwrap_or_default();
+        record.throwable = element_text(event, "log4j:Throwable");
+        record
+    }
+}
+
+/// The text of the first `<name>...</name>` in `event`, which is CDATA sections or escaped text.
+fn element_text(event: &str, name: &str) -> Option<String> {
+    let start = event.find(&format!("<{name}>"))? + name.len() + 2;
+    let end = start + event[start..].find(&format!("</{name}>"))?;
+    let content = &event[start..end];
+    if content.contains("<![CDATA[") {
+        // `]]>` in the text is split into two sections by log4j
+        Some(
+            CDATA
+                .captures_iter(content)
+                .map(|captures| captures[1].to_string())
+                .collect(),
+        )
+    } else {
+        Some(unescape(content))
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Turns lines of the game output into log records
+///
+/// An XML event spans several lines, so a record is only returned once the event is complete.
+#[derive(Debug, Default)]
+pub struct LogParser {
+    event: Option<String>,
+}
+
+impl LogParser {
+    /// Parse a line without the line break.
+    pub fn push_line(&mut self, line: &str) -> Option<LogRecord> {
+        match &mut self.event {
+            Some(event) => {
+                event.push('\n');
+                event.push_str(line);
+            }
+            None if line.trim_start().starts_with("<log4j:Event") => {
+                self.event = Some(line.to_string());
+            }
+            None => return Some(LogRecord::plain(line)),
+        }
+        if line.contains("</log4j:Event>") {
+            return self.event.take().map(|event| LogRecord::xml(&event));
+        }
+        None
+    }
+}
+
+/// The records which are not received yet.
+struct LogBuffer {
+    records: VecDeque<LogRecord>,
+    capacity: usize,
+    dropped: usize,
+    senders: usize,
+    waker: Option<Waker>,
+}
+
+/// Create a log stream keeping up to `capacity` records, the oldest ones are dropped when it
+/// is full.
+pub(crate) fn log_channel(capacity: usize) -> (LogSender, LogStream) {
+    let buffer = Arc::new(Mutex::new(LogBuffer {
+        records: VecDeque::new(),
+        capacity,
+        dropped: 0,
+        senders: 1,
+        waker: None,
+    }));
+    (LogSender(buffer.clone()), LogStream(buffer))
+}
+
+/// The sending side of a [`LogStream`], the stream ends once all senders are dropped.
+pub(crate) struct LogSender(Arc<Mutex<LogBuffer>>);
+
+impl LogSender {
+    pub(crate) fn send(&self, record: LogRecord) {
+        let mut buffer = self.0.lock().unwrap();
+        if buffer.records.len() == buffer.capacity {
+            buffer.records.pop_front();
+            buffer.dropped += 1;
+        }
+        buffer.records.push_back(record);
+        if let Some(waker) = buffer.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Clone for LogSender {
+    fn clone(&self) -> Self {
+        self.0.lock().unwrap().senders += 1;
+        Self(self.0.clone())
+    }
+}
+
+impl Drop for LogSender {
+    fn drop(&mut self) {
+        let mut buffer = self.0.lock().unwrap();
+        buffer.senders -= 1;
+        if buffer.senders == 0 {
+            if let Some(waker) = buffer.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// The log records of the game, see [`GameProcess::take_logs`].
+///
+/// It ends when the game exits.
+///
+/// [`GameProcess::take_logs`]: super::launcher::GameProcess::take_logs
+pub struct LogStream(Arc<Mutex<LogBuffer>>);
+
+impl LogStream {
+    /// The next record, `None` after the game exited and all records are received.
+    pub async fn recv(&mut self) -> Option<LogRecord> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+
+    /// How many of the oldest records were dropped because they were not received in time.
+    pub fn dropped(&self) -> usize {
+        self.0.lock().unwrap().dropped
+    }
+}
+
+impl Stream for LogStream {
+    type Item = LogRecord;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut buffer = self.0.lock().unwrap();
+        if let Some(record) = buffer.records.pop_front() {
+            return Poll::Ready(Some(record));
+        }
+        if buffer.senders == 0 {
+            return Poll::Ready(None);
+        }
+        buffer.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_xml_events() {
+        let output = r#"<log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1692000000123" level="INFO" thread="Render thread">
+  <log4j:Message><![CDATA[Setting user: Steve]]></log4j:Message>
+</log4j:Event>
+<log4j:Event logger="ehb" timestamp="1692000000456" level="ERROR" thread="Worker &quot;1&quot;">
+  <log4j:Message><![CDATA[a ]]]]><![CDATA[> b]]></log4j:Message>
+  <log4j:Throwable><![CDATA[java.lang.NullPointerException
+	at ehb.a(SourceFile:12)
+]]></log4j:Throwable>
+</log4j:Event>
+[12:34:56] [Render thread/WARN]: Missing sound for event
+[12:34:57] [main/INFO] [net.minecraftforge.fml.loading.ModDiscoverer/SCAN]: Found mods
+	at java.base/java.lang.Thread.run(Thread.java:833)"#;
+        let mut parser = LogParser::default();
+        let records: Vec<_> = output
+            .lines()
+            .filter_map(|line| parser.push_line(line))
+            .collect();
+        assert_eq!(records.len(), 5);
+        assert_eq!(
+            records[0],
+            LogRecord {
+                timestamp: Some(1692000000123),
+                time: None,
+                level: LogLevel::Info,
+                thread: Some("Render thread".to_string()),
+                logger: Some("net.minecraft.client.Minecraft".to_string()),
+                message: "Setting user: Steve".to_string(),
+                throwable: None,
+            }
+        );
+        assert_eq!(records[1].level, LogLevel::Error);
+        assert_eq!(records[1].thread.as_deref(), Some("Worker \"1\""));
+        assert_eq!(records[1].message, "a ]]> b");
+        assert_eq!(
+            records[1].throwable.as_deref(),
+            Some("java.lang.NullPointerException\n\tat ehb.a(SourceFile:12)\n")
+        );
+        assert_eq!(
+            records[2],
+            LogRecord {
+                timestamp: None,
+                time: Some("12:34:56".to_string()),
+                level: LogLevel::Warn,
+                thread: Some("Render thread".to_string()),
+                logger: None,
+                message: "Missing sound for event".to_string(),
+                throwable: None,
+            }
+        );
+        assert_eq!(
+            records[3].logger.as_deref(),
+            Some("net.minecraftforge.fml.loading.ModDiscoverer/SCAN")
+        );
+        assert_eq!(records[3].message, "Found mods");
+        assert_eq!(records[4].level, LogLevel::Unknown);
+    }
+
+    #[tokio::test]
+    async fn keep_newest_records() {
+        let (sender, mut logs) = log_channel(2);
+        for line in ["1", "2", "3"] {
+            sender.send(LogRecord::plain(line));
+        }
+        drop(sender);
+        assert_eq!(logs.recv().await.unwrap().message, "2");
+        assert_eq!(logs.recv().await.unwrap().message, "3");
+        assert!(logs.recv().await.is_none());
+        assert_eq!(logs.dropped(), 1);
+    }
+}
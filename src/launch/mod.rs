@@ -18,9 +18,8 @@
 
 //! A launcher for game
 //!
-//! This module contains the [`Launcher`] [`LaunchOptions`] struct, the `launch` function for
-//! launching a game, and several error types that may result from
-//! working with [`Launcher`].
+//! This module contains the [`Launcher`] [`LaunchOptions`] struct for launching a game, and the
+//! [`GameProcess`] handle of the launched game.
 //!
 //! # Examples
 //!
@@ -54,17 +53,32 @@
 //! game using [`Launcher::launch()`].
 //!
 //! ```
-//! use aml_core::core::folder::MinecraftLocation;
 //! use aml_core::core::JavaExec;
-//! use aml_core::launch::launch::Launcher;
+//! use aml_core::launch::launcher::{LaunchEvent, Launcher};
 //! use aml_core::launch::options::LaunchOptions;
-//! use crate::aml_core::launch::launch::Launch;
 //!
 //!  async fn fn_name3(options: LaunchOptions) {
-//!     let mut launcher = Launcher::from_options(options, JavaExec::new("/path/to/java-home").await);
-//!     launcher.launch(None, None, None, None).await.unwrap();
+//!     let launcher = Launcher::from_options(options, JavaExec::new("/path/to/java-home").await)
+//!         .on_event(Box::new(|event| {
+//!             if let LaunchEvent::Crashed(status) = event {
+//!                 println!("Game crashed: {status}");
+//!             }
+//!         }));
+//!     let mut game = launcher.launch().await.unwrap();
+//!     println!("pid: {:?}", game.pid());
+//!     game.wait().await.unwrap();
 //! }
 //! ```
+//!
+//! [`Launcher`]: launcher::Launcher
+//! [`Launcher::launch()`]: launcher::Launcher::launch
+//! [`GameProcess`]: launcher::GameProcess
+//! [`LaunchOptions`]: options::LaunchOptions
 
 pub mod argument;
+pub mod crash;
+pub mod launcher;
+pub mod log;
+pub mod natives;
 pub mod options;
+pub mod script;
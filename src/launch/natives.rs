@@ -0,0 +1,248 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Extract the native libraries of a version before launching.
+//!
+//! The natives are extracted to the natives folder (`versions/<id>/cvl-natives` by default) once,
+//! a manifest with the sha1 of the native libraries is saved in that folder, and the extraction
+//! is skipped as long as the libraries and the extracted files don't change.
+
+use std::{
+    fs::File,
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use zip::ZipArchive;
+
+use crate::{
+    core::{folder::MinecraftLocation, version::ResolvedVersion},
+    utils::{download::file_sha1, unzip::decompression_all},
+};
+
+/// The file name of the manifest in the natives folder.
+pub const NATIVES_MANIFEST: &str = ".natives.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct NativeLibraryManifest {
+    path: String,
+    sha1: String,
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct NativesManifest {
+    libraries: Vec<NativeLibraryManifest>,
+
+    /// The extracted files, relative to the natives folder.
+    files: Vec<PathBuf>,
+}
+
+/// Extract the native libraries of the version to `natives_dir`.
+///
+/// Entries matching `extract.exclude` of the library are skipped. If the natives folder is
+/// already up to date, nothing is extracted. Otherwise the files listed in the previous
+/// manifest are removed and the libraries are extracted again, overwriting existing files.
+/// Other files in the folder are kept.
+pub async fn prepare_natives(
+    version: &ResolvedVersion,
+    minecraft: &MinecraftLocation,
+    natives_dir: &Path,
+) -> Result<()> {
+    let mut libraries = Vec::new();
+    for library in version.libraries.iter().filter(|lib| lib.is_native_library) {
+        let path = minecraft.get_library_by_path(&library.download_info.path);
+        let sha1 = file_sha1(&path).await.map_err(|err| {
+            anyhow!(
+                "Native library {} is not readable: {err}",
+                path.to_string_lossy()
+            )
+        })?;
+        libraries.push(NativeLibraryManifest {
+            path: library.download_info.path.clone(),
+            sha1,
+            exclude: library.extract_exclude.clone(),
+        });
+    }
+
+    let manifest_path = natives_dir.join(NATIVES_MANIFEST);
+    let previous = match tokio::fs::read_to_string(&manifest_path).await {
+        Ok(manifest) => serde_json::from_str::<NativesManifest>(&manifest).ok(),
+        Err(_) => None,
+    };
+    if let Some(previous) = previous {
+        let files_exist = previous
+            .files
+            .iter()
+            .all(|file| natives_dir.join(file).is_file());
+        if previous.libraries == libraries && files_exist {
+            return Ok(());
+        }
+        // only the files extracted before are removed, the folder may have other files
+        for file in previous.files.iter().filter(|file| {
+            file.components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        }) {
+            match tokio::fs::remove_file(natives_dir.join(file)).await {
+                Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+                _ => {}
+            }
+        }
+        tokio::fs::remove_file(&manifest_path).await?;
+    }
+    tokio::fs::create_dir_all(natives_dir).await?;
+
+    let minecraft = minecraft.clone();
+    let natives_dir = natives_dir.to_path_buf();
+    let manifest = tokio::task::spawn_blocking(move || -> Result<NativesManifest> {
+        let mut files = Vec::new();
+        for library in &libraries {
+            let path = minecraft.get_library_by_path(&library.path);
+            let mut zip_archive = ZipArchive::new(File::open(&path)?).map_err(|err| {
+                anyhow!("Native library {} is broken: {err}", path.to_string_lossy())
+            })?;
+            files.extend(decompression_all(
+                &mut zip_archive,
+                &natives_dir,
+                &library.exclude,
+            )?);
+        }
+        Ok(NativesManifest { libraries, files })
+    })
+    .await??;
+
+    tokio::fs::write(manifest_path, serde_json::to_string(&manifest)?).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+    use crate::core::version::{JavaVersion, LibraryDownload, ResolvedLibrary};
+
+    fn version_with_native(path: &str) -> ResolvedVersion {
+        ResolvedVersion {
+            id: "1.12.2".to_string(),
+            arguments: None,
+            main_class: "net.minecraft.client.main.Main".to_string(),
+            asset_index: None,
+            assets: "1.12".to_string(),
+            downloads: None,
+            libraries: vec![ResolvedLibrary {
+                download_info: LibraryDownload {
+                    sha1: None,
+                    size: None,
+                    url: String::new(),
+                    path: path.to_string(),
+                },
+                is_native_library: true,
+                extract_exclude: vec!["META-INF/".to_string()],
+            }],
+            minimum_launcher_version: 0,
+            release_time: String::new(),
+            time: String::new(),
+            version_type: "release".to_string(),
+            logging: None,
+            java_version: JavaVersion {
+                component: "jre-legacy".to_string(),
+                major_version: 8,
+            },
+            inheritances: vec![],
+            path_chain: vec![],
+        }
+    }
+
+    fn write_native_jar(jar: &Path, native: &str) {
+        std::fs::create_dir_all(jar.parent().unwrap()).unwrap();
+        let mut writer = ZipWriter::new(File::create(jar).unwrap());
+        writer
+            .start_file("META-INF/MANIFEST.MF", FileOptions::default())
+            .unwrap();
+        writer.write_all(b"Manifest-Version: 1.0").unwrap();
+        writer.start_file(native, FileOptions::default()).unwrap();
+        writer.write_all(b"native").unwrap();
+        writer.finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn extract_natives_once() {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let jar = minecraft.get_library_by_path("lwjgl-platform-natives-linux.jar");
+        write_native_jar(&jar, "liblwjgl64.so");
+
+        let version = version_with_native("lwjgl-platform-natives-linux.jar");
+        let natives_dir = minecraft.get_natives_root(&version.id);
+        // a file which was there before is kept
+        std::fs::create_dir_all(&natives_dir).unwrap();
+        std::fs::write(natives_dir.join("other.txt"), b"other").unwrap();
+        prepare_natives(&version, &minecraft, &natives_dir)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read(natives_dir.join("liblwjgl64.so")).unwrap(),
+            b"native"
+        );
+        assert!(!natives_dir.join("META-INF").exists());
+
+        // up to date, not extracted again
+        std::fs::write(natives_dir.join("liblwjgl64.so"), b"changed").unwrap();
+        prepare_natives(&version, &minecraft, &natives_dir)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read(natives_dir.join("liblwjgl64.so")).unwrap(),
+            b"changed"
+        );
+
+        // a missing file is extracted again
+        std::fs::remove_file(natives_dir.join("liblwjgl64.so")).unwrap();
+        prepare_natives(&version, &minecraft, &natives_dir)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read(natives_dir.join("liblwjgl64.so")).unwrap(),
+            b"native"
+        );
+
+        // the files of a changed library replace the files extracted before
+        write_native_jar(&jar, "liblwjgl.so");
+        prepare_natives(&version, &minecraft, &natives_dir)
+            .await
+            .unwrap();
+        assert!(natives_dir.join("liblwjgl.so").is_file());
+        assert!(!natives_dir.join("liblwjgl64.so").exists());
+        assert_eq!(
+            std::fs::read(natives_dir.join("other.txt")).unwrap(),
+            b"other"
+        );
+
+        // a missing library is an error
+        let version = version_with_native("missing.jar");
+        assert!(prepare_natives(&version, &minecraft, &natives_dir)
+            .await
+            .is_err());
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
+    }
+}
@@ -18,22 +18,30 @@
 
 use std::{collections::HashMap, path::PathBuf};
 
-use anyhow::{anyhow, Result};
-use regex::Regex;
-use serde_json::Value;
+use anyhow::Result;
 
-use crate::core::{folder::MinecraftLocation, version::Version};
+use crate::{
+    auth::{Account, OfflineAccount},
+    core::{folder::MinecraftLocation, version::Version},
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct GameProfile {
     pub name: String,
     pub uuid: String,
 }
 
-#[derive(Debug, Clone)]
+/// `${user_type}` of the game arguments
+#[derive(Debug, Clone, PartialEq)]
 pub enum UserType {
+    /// A Yggdrasil account, of Mojang or of an authlib-injector server.
     Mojang,
+
+    /// An offline account.
     Legacy,
+
+    /// A Microsoft account.
+    Msa,
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +50,17 @@ pub struct Server {
     pub port: Option<u16>,
 }
 
+/// Start the game directly in a world, a server or a realm, supported since 23w14a.
+#[derive(Debug, Clone)]
+pub enum QuickPlay {
+    /// The folder name of the world in `saves`
+    Singleplayer(String),
+    /// The address of the server, like `mc.example.com:25565`
+    Multiplayer(String),
+    /// The id of the realm
+    Realms(String),
+}
+
 #[derive(Debug, Clone)]
 pub struct YggdrasilAgent {
     /// The jar file path of the authlib-injector
@@ -54,7 +73,10 @@ pub struct YggdrasilAgent {
     pub prefetched: Option<String>,
 }
 
-/// Game process priority, invalid on windows
+/// Game process priority
+///
+/// On Windows this is the priority class of the process. On other platforms the priority can only
+/// be lowered (with `nice`), `High` and `AboveNormal` are the same as `Normal`.
 #[derive(Debug, Clone)]
 pub enum ProcessPriority {
     High,
@@ -129,6 +151,10 @@ pub struct LaunchOptions {
     /// Directly launch to a server.
     pub(crate) server: Option<Server>,
 
+    /// Directly launch to a world, a server or a realm with the quick play arguments of
+    /// version.json.
+    pub(crate) quick_play: Option<QuickPlay>,
+
     /// window width
     pub(crate) width: u32,
 
@@ -147,7 +173,6 @@ pub struct LaunchOptions {
 
     pub(crate) is_demo: bool,
 
-    // Todo: yggdrasilAgent
     /// Add `-Dfml.ignoreInvalidMinecraftCertificates=true` to jvm argument
     pub(crate) ignore_invalid_minecraft_certificates: bool,
 
@@ -167,10 +192,14 @@ pub struct LaunchOptions {
     /// The version of launched Minecraft. Can be either resolved version or version string
     pub(crate) version: Version,
 
-    /// Enable features. Not really in used...
-    pub(crate) features: HashMap<String, Value>,
+    /// Enabled features, used to check the rules of the arguments in version.json.
+    ///
+    /// Like `has_quick_plays_support`, `is_quick_play_singleplayer`. `is_demo_user` and
+    /// `has_custom_resolution` are set from `is_demo` and `fullscreen`, and the quick play
+    /// features from `quick_play`, unless present.
+    pub(crate) features: HashMap<String, bool>,
 
-    /// Game process priority, invalid on windows
+    /// Game process priority
     pub(crate) process_priority: ProcessPriority,
 
     /// Support yushi's yggdrasil agent <https://github.com/to2mbn/authlib-injector/wiki>
@@ -182,6 +211,9 @@ pub struct LaunchOptions {
 
     pub(crate) minecraft_location: MinecraftLocation,
 
+    /// Extra environment variables of the game process.
+    pub(crate) env: HashMap<String, String>,
+
     pub(crate) native_path: PathBuf,
 }
 
@@ -190,15 +222,13 @@ impl LaunchOptions {
     pub async fn new(version_id: &str, minecraft: &MinecraftLocation) -> Result<Self> {
         let version_json_path = minecraft.get_version_json(version_id);
         let raw_version_json = tokio::fs::read_to_string(version_json_path).await?;
-        let version_json: Version = serde_json::from_str((&raw_version_json).as_ref())?;
+        let version_json: Version = serde_json::from_str(raw_version_json.as_ref())?;
+        let account: Account = OfflineAccount::new("Steve")?.into();
 
         Ok(Self {
-            game_profile: GameProfile {
-                name: "Steve".to_string(),
-                uuid: uuid::Uuid::new_v4().to_string().replace('-', ""),
-            },
-            access_token: uuid::Uuid::new_v4().to_string().replace('-', ""),
-            user_type: UserType::Mojang,
+            game_profile: account.profile,
+            access_token: account.access_token,
+            user_type: account.user_type,
             properties: "{}".to_string(),
             launcher_name: "AmethystLauncher".to_string(),
             launcher_version: "0.0.1".to_string(),
@@ -212,6 +242,7 @@ impl LaunchOptions {
             min_memory: 128,
             max_memory: 2048,
             server: None,
+            quick_play: None,
             width: 854,
             height: 480,
             fullscreen: false,
@@ -228,39 +259,52 @@ impl LaunchOptions {
             version_id: version_id.to_string(),
             gc: GC::G1,
             minecraft_location: minecraft.clone(),
+            env: HashMap::new(),
             native_path: minecraft.get_natives_root(version_id),
         })
     }
 
+    /// Launch as `account`, the default is the offline account `Steve`.
+    pub fn set_account<A: Into<Account>>(&mut self, account: A) {
+        let account = account.into();
+        self.game_profile = account.profile;
+        self.access_token = account.access_token;
+        self.user_type = account.user_type;
+    }
+
+    /// Start the game with authlib-injector, for a Yggdrasil account, see
+    /// [`YggdrasilClient::agent`](crate::auth::yggdrasil::YggdrasilClient::agent).
+    pub fn set_yggdrasil_agent(&mut self, agent: Option<YggdrasilAgent>) {
+        self.yggdrasil_agent = agent;
+    }
+
+    /// Set an environment variable of the game process, like `__GL_THREADED_OPTIMIZATIONS`.
+    pub fn set_env<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        self.env.insert(key.into(), value.into());
+    }
+
+    pub fn set_process_priority(&mut self, priority: ProcessPriority) {
+        self.process_priority = priority;
+    }
+
+    /// Enable or disable a feature of the rules in version.json, like `has_quick_plays_support`.
+    pub fn set_feature(&mut self, name: &str, enabled: bool) {
+        self.features.insert(name.to_string(), enabled);
+    }
+
+    pub fn set_quick_play(&mut self, quick_play: Option<QuickPlay>) {
+        self.quick_play = quick_play;
+    }
+
     pub async fn new_forge_options(
         version_id: &str,
         minecraft: &MinecraftLocation,
     ) -> Result<Self> {
-        let mut default = LaunchOptions::new(version_id, &minecraft).await?;
-
-        let version = minecraft.get_version_json(version_id);
-        let version = tokio::fs::read_to_string(version).await?;
-        let version = serde_json::from_str::<Version>(&version)?;
-
-        if let Some(arguments) = version.minecraft_arguments {
-            let tweak_class_regex = Regex::new(r"--tweakClass\s+.+").unwrap();
-            let forge_arguments = tweak_class_regex
-                .captures(&arguments)
-                .ok_or(anyhow!("tweakClass not found"))?
-                .get(0)
-                .ok_or(anyhow!("tweakClass not found"))?
-                .as_str();
-            default.extra_mc_args.extend(
-                forge_arguments
-                    .split(" ")
-                    .map(|x| x.to_string())
-                    .collect::<Vec<String>>(),
-            );
-        }
+        let mut default = LaunchOptions::new(version_id, minecraft).await?;
 
         default.extra_jvm_args.extend(vec![
-            "\"-Dfml.ignoreInvalidMinecraftCertificates=true\"".to_string(),
-            "\"-Dfml.ignorePatchDiscrepancies=true\"".to_string(),
+            "-Dfml.ignoreInvalidMinecraftCertificates=true".to_string(),
+            "-Dfml.ignorePatchDiscrepancies=true".to_string(),
         ]);
 
         Ok(default)
@@ -0,0 +1,270 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Export a standalone start script
+//!
+//! The launcher never needs a script, see [`Launcher`](super::launcher::Launcher). This is for
+//! users who want to start the game without the launcher. Every argument is quoted for the shell,
+//! so paths and player names with spaces or quotes are kept as they are.
+//!
+//! # Example
+//!
+//! ```
+//! use aml_core::core::JavaExec;
+//! use aml_core::launch::argument::LaunchArguments;
+//! use aml_core::launch::options::LaunchOptions;
+//! use aml_core::launch::script::{write_launch_script, ScriptKind};
+//!
+//! async fn fn_name(arguments: LaunchArguments, options: LaunchOptions, java: JavaExec) {
+//!     write_launch_script("start.sh", ScriptKind::Shell, &arguments, &options, &java)
+//!         .await
+//!         .unwrap();
+//! }
+//! ```
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::core::JavaExec;
+
+use super::{
+    argument::LaunchArguments,
+    options::{LaunchOptions, ProcessPriority},
+};
+
+/// The type of the start script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptKind {
+    /// A POSIX shell script.
+    Shell,
+
+    /// A PowerShell script. The process priority is not applied.
+    PowerShell,
+}
+
+/// Generate a script which starts the game like [`Launcher::spawn`] does: in the game directory,
+/// with the extra environment variables and the process priority.
+///
+/// [`Launcher::spawn`]: super::launcher::Launcher::spawn
+pub fn launch_script(
+    kind: ScriptKind,
+    arguments: &LaunchArguments,
+    options: &LaunchOptions,
+    java: &JavaExec,
+) -> String {
+    let game_path = options.game_path.to_string_lossy();
+    let java = java.binary.to_string_lossy();
+    let mut env: Vec<_> = options.env.iter().collect();
+    env.sort();
+    match kind {
+        ScriptKind::Shell => {
+            let mut script = format!("#!/bin/sh\ncd -- {} || exit 1\n", quote_sh(&game_path));
+            let mut command = vec!["exec".to_string()];
+            if !env.is_empty() {
+                command.push("env".to_string());
+                command.extend(
+                    env.iter()
+                        .map(|(key, value)| quote_sh(&format!("{key}={value}"))),
+                );
+            }
+            match options.process_priority {
+                ProcessPriority::BelowNormal => command.push("nice -n 5".to_string()),
+                ProcessPriority::Low => command.push("nice -n 10".to_string()),
+                _ => (),
+            }
+            command.push(quote_sh(&java));
+            command.extend(arguments.as_slice().iter().map(|arg| quote_sh(arg)));
+            script.push_str(&command.join(" \\\n  "));
+            script.push('\n');
+            script
+        }
+        ScriptKind::PowerShell => {
+            let mut script = format!(
+                "Set-Location -LiteralPath {}\n",
+                quote_powershell(&game_path)
+            );
+            for (key, value) in env {
+                script.push_str(&format!(
+                    "[Environment]::SetEnvironmentVariable({}, {})\n",
+                    quote_powershell(key),
+                    quote_powershell(value)
+                ));
+            }
+            let mut command = vec![format!("& {}", quote_powershell(&java))];
+            command.extend(arguments.as_slice().iter().map(|arg| quote_powershell(arg)));
+            script.push_str(&command.join(" `\n  "));
+            script.push_str("\nexit $LASTEXITCODE\n");
+            script
+        }
+    }
+}
+
+/// Write the start script to `path`, on unix it is made executable.
+pub async fn write_launch_script<P: AsRef<Path>>(
+    path: P,
+    kind: ScriptKind,
+    arguments: &LaunchArguments,
+    options: &LaunchOptions,
+    java: &JavaExec,
+) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, launch_script(kind, arguments, options, java)).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).await?;
+    }
+    Ok(())
+}
+
+/// Quote for POSIX shells, everything in single quotes is literal except the single quote itself.
+fn quote_sh(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Quote for PowerShell, a single quote (including the typographic ones) is escaped by doubling it.
+fn quote_powershell(arg: &str) -> String {
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('\'');
+    for c in arg.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            quoted.push(c);
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::folder::MinecraftLocation;
+
+    const ARGUMENTS: &[&str] = &["%s\\n", "Steve Jobs", "it's", "$HOME `id`", ""];
+
+    async fn options(minecraft: &MinecraftLocation) -> LaunchOptions {
+        let version_json = minecraft.get_version_json("1.20.1");
+        std::fs::create_dir_all(version_json.parent().unwrap()).unwrap();
+        std::fs::write(&version_json, r#"{"id": "1.20.1"}"#).unwrap();
+        let mut options = LaunchOptions::new("1.20.1", minecraft).await.unwrap();
+        options.game_path = minecraft.root.join("game dir");
+        options.set_env("GAME_NAME", "Amethyst's game");
+        options
+    }
+
+    fn arguments() -> LaunchArguments {
+        LaunchArguments::from(
+            ARGUMENTS
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn quote_arguments() {
+        assert_eq!(quote_sh("-Xmx2048M"), "-Xmx2048M");
+        assert_eq!(quote_sh(""), "''");
+        assert_eq!(quote_sh("it's"), r"'it'\''s'");
+        assert_eq!(quote_powershell("it's"), "'it''s'");
+        assert_eq!(
+            quote_powershell("it\u{2019}s $x"),
+            "'it\u{2019}\u{2019}s $x'"
+        );
+    }
+
+    #[tokio::test]
+    async fn export_powershell_script() {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let options = options(&minecraft).await;
+        let java = JavaExec {
+            binary: "C:\\Program Files\\Java\\bin\\java.exe".into(),
+        };
+        let script = launch_script(ScriptKind::PowerShell, &arguments(), &options, &java);
+        assert!(script
+            .contains("[Environment]::SetEnvironmentVariable('GAME_NAME', 'Amethyst''s game')\n"));
+        assert!(script.contains(
+            "& 'C:\\Program Files\\Java\\bin\\java.exe' `\n  '%s\\n' `\n  'Steve Jobs' `\n  'it''s' `\n  '$HOME `id`' `\n  ''\nexit $LASTEXITCODE\n"
+        ));
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_shell_script() {
+        let minecraft =
+            MinecraftLocation::new(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let options = options(&minecraft).await;
+        std::fs::create_dir_all(&options.game_path).unwrap();
+        let script_path = minecraft.root.join("start.sh");
+        let java = JavaExec {
+            binary: "printf".into(),
+        };
+        write_launch_script(
+            &script_path,
+            ScriptKind::Shell,
+            &arguments(),
+            &options,
+            &java,
+        )
+        .await
+        .unwrap();
+
+        let output = tokio::process::Command::new(&script_path)
+            .output()
+            .await
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "Steve Jobs\nit's\n$HOME `id`\n\n"
+        );
+
+        // the environment and the working directory
+        let java = JavaExec {
+            binary: "sh".into(),
+        };
+        let arguments = LaunchArguments::from(vec![
+            "-c".to_string(),
+            "echo \"$GAME_NAME\"; pwd -P".to_string(),
+        ]);
+        write_launch_script(&script_path, ScriptKind::Shell, &arguments, &options, &java)
+            .await
+            .unwrap();
+        let output = tokio::process::Command::new(&script_path)
+            .output()
+            .await
+            .unwrap();
+        let game_path = std::fs::canonicalize(&options.game_path).unwrap();
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("Amethyst's game\n{}\n", game_path.to_string_lossy())
+        );
+        std::fs::remove_dir_all(&minecraft.root).unwrap();
+    }
+}
@@ -16,8 +16,10 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod auth;
 pub mod core;
 pub mod install;
+pub mod java;
 pub mod launch;
 pub mod utils;
 pub mod game_data;
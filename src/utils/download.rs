@@ -0,0 +1,714 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The concurrent downloader
+//!
+//! Executes the `Vec<Download>` generated by the install module. Every file is written to a
+//! temporary file next to the target and renamed into place once it is complete (and its sha1
+//! matches, if one is provided), so an interrupted download never leaves a broken file behind.
+//!
+//! Failed requests are retried with exponential backoff. If the server supports HTTP range
+//! requests, a retry continues from where the broken connection stopped instead of starting over.
+//! When a source keeps failing, the next source of the [`NetworkOptions`] is used.
+//!
+//! # Example
+//!
+//! ```
+//! use aml_core::core::Download;
+//! use aml_core::utils::download::Downloader;
+//!
+//! async fn fn_name(downloads: Vec<Download>) {
+//!     let downloader = Downloader::default()
+//!         .concurrency(32)
+//!         .retries(5)
+//!         .on_progress(Box::new(|progress| {
+//!             println!("{}/{} files, {} bytes", progress.completed, progress.total, progress.downloaded_bytes);
+//!         }));
+//!     let report = downloader.download_all(downloads).await;
+//!     for failed in report.failed {
+//!         println!("{}: {}", failed.download.url, failed.error);
+//!     }
+//! }
+//! ```
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use futures::{stream, StreamExt};
+use reqwest::{header, StatusCode};
+use sha1::{Digest, Sha1};
+use tokio::{fs, io::AsyncReadExt, io::AsyncWriteExt};
+
+pub use crate::core::Download;
+use crate::core::{
+    source::{network_options, NetworkOptions},
+    HTTP_CLIENT,
+};
+
+/// The default number of files downloaded at the same time.
+pub const DEFAULT_CONCURRENCY: usize = 16;
+
+/// The default number of retries after the first attempt of a file failed.
+pub const DEFAULT_RETRIES: usize = 3;
+
+/// The default delay before the first retry, it doubles after every failed attempt.
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Aggregate progress of a download list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct DownloadProgress {
+    /// Files that are done, including the skipped ones.
+    pub completed: usize,
+
+    /// Files that already existed with a matching sha1 and were not downloaded again.
+    pub skipped: usize,
+
+    /// Files that still failed after all retries.
+    pub failed: usize,
+
+    /// Files in the download list.
+    pub total: usize,
+
+    /// Bytes received from the network so far.
+    pub downloaded_bytes: u64,
+}
+
+/// Why a file could not be downloaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadError {
+    /// The server answered with an error status code.
+    Status(u16),
+
+    /// The connection failed or was closed before the file was complete.
+    Network(String),
+
+    /// The downloaded file does not have the expected sha1.
+    Sha1Mismatch { expected: String, actual: String },
+
+    /// The file could not be written.
+    Io(String),
+}
+
+impl DownloadError {
+    /// Client errors like `404 Not Found` will not change when asked again.
+    fn is_retryable(&self) -> bool {
+        match self {
+            DownloadError::Status(status) => *status == 408 || *status == 429 || *status >= 500,
+            DownloadError::Io(_) => false,
+            _ => true,
+        }
+    }
+}
+
+impl Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DownloadError::Status(status) => write!(f, "server responded with status {status}"),
+            DownloadError::Network(error) => write!(f, "network error: {error}"),
+            DownloadError::Sha1Mismatch { expected, actual } => {
+                write!(f, "sha1 mismatch: expected {expected}, got {actual}")
+            }
+            DownloadError::Io(error) => write!(f, "io error: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for DownloadError {}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(value: reqwest::Error) -> Self {
+        match value.status() {
+            Some(status) => DownloadError::Status(status.as_u16()),
+            None => DownloadError::Network(value.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for DownloadError {
+    fn from(value: std::io::Error) -> Self {
+        DownloadError::Io(value.to_string())
+    }
+}
+
+/// A file that could not be downloaded.
+#[derive(Debug, Clone)]
+pub struct FailedDownload {
+    pub download: Download,
+
+    /// The error of the last attempt.
+    pub error: DownloadError,
+
+    /// How many times the file was requested.
+    pub attempts: usize,
+}
+
+/// The result of a download list.
+#[derive(Debug, Clone, Default)]
+pub struct DownloadReport {
+    pub progress: DownloadProgress,
+    pub failed: Vec<FailedDownload>,
+}
+
+impl DownloadReport {
+    /// Whether every file is in place.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Turn the report into an error if any file failed.
+    pub fn into_result(self) -> Result<DownloadProgress> {
+        match self.failed.first() {
+            None => Ok(self.progress),
+            Some(first) => Err(anyhow!(
+                "{} of {} files failed to download, first: {} ({})",
+                self.failed.len(),
+                self.progress.total,
+                first.download.url,
+                first.error
+            )),
+        }
+    }
+}
+
+/// Runs download lists with bounded concurrency.
+///
+/// Use `Downloader::default()` to create a new instance, and use `Downloader::concurrency()`
+/// `Downloader::network_options()` `Downloader::retries()` `Downloader::retry_delay()`
+/// `Downloader::on_progress()` to configure it.
+///
+/// Every file is tried on each source of the network options in order, a source is only given up
+/// after all its retries failed.
+pub struct Downloader {
+    concurrency: usize,
+    network_options: NetworkOptions,
+    retries: usize,
+    retry_delay: Duration,
+    on_progress: Box<dyn Fn(DownloadProgress) + Send + Sync>,
+}
+
+impl Default for Downloader {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+            network_options: network_options(),
+            retries: DEFAULT_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            on_progress: Box::new(|_| {}),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ProgressCounter {
+    completed: AtomicUsize,
+    skipped: AtomicUsize,
+    failed: AtomicUsize,
+    total: usize,
+    downloaded_bytes: AtomicU64,
+}
+
+impl ProgressCounter {
+    fn snapshot(&self) -> DownloadProgress {
+        DownloadProgress {
+            completed: self.completed.load(Ordering::SeqCst),
+            skipped: self.skipped.load(Ordering::SeqCst),
+            failed: self.failed.load(Ordering::SeqCst),
+            total: self.total,
+            downloaded_bytes: self.downloaded_bytes.load(Ordering::SeqCst),
+        }
+    }
+}
+
+impl Downloader {
+    /// Set how many files are downloaded at the same time, at least one.
+    pub fn concurrency(self, concurrency: usize) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            ..self
+        }
+    }
+
+    /// Set the sources to download from, instead of the ones from `set_network_options()`.
+    pub fn network_options(self, network_options: NetworkOptions) -> Self {
+        Self {
+            network_options,
+            ..self
+        }
+    }
+
+    /// Set how many times a failed file is requested again from the same source.
+    pub fn retries(self, retries: usize) -> Self {
+        Self { retries, ..self }
+    }
+
+    /// Set the delay before the first retry, it doubles after every failed attempt.
+    pub fn retry_delay(self, retry_delay: Duration) -> Self {
+        Self {
+            retry_delay,
+            ..self
+        }
+    }
+
+    /// Register the progress listener, it is called every time a chunk is received or a file is done
+    pub fn on_progress(self, on_progress: Box<dyn Fn(DownloadProgress) + Send + Sync>) -> Self {
+        Self {
+            on_progress,
+            ..self
+        }
+    }
+
+    /// Download all the files.
+    ///
+    /// A failed file does not stop the others, check `DownloadReport::failed` for them.
+    pub async fn download_all(&self, downloads: Vec<Download>) -> DownloadReport {
+        let counter = Arc::new(ProgressCounter {
+            total: downloads.len(),
+            ..Default::default()
+        });
+        let failed = Mutex::new(Vec::new());
+        stream::iter(downloads)
+            .for_each_concurrent(self.concurrency, |download| {
+                let counter = counter.clone();
+                let failed = &failed;
+                async move {
+                    match self.download_with_retry(&download, &counter).await {
+                        Ok(downloaded) => {
+                            counter.completed.fetch_add(1, Ordering::SeqCst);
+                            if !downloaded {
+                                counter.skipped.fetch_add(1, Ordering::SeqCst);
+                            }
+                        }
+                        Err((error, attempts)) => {
+                            counter.failed.fetch_add(1, Ordering::SeqCst);
+                            failed.lock().unwrap().push(FailedDownload {
+                                download,
+                                error,
+                                attempts,
+                            });
+                        }
+                    }
+                    (self.on_progress)(counter.snapshot());
+                }
+            })
+            .await;
+        DownloadReport {
+            progress: counter.snapshot(),
+            failed: failed.into_inner().unwrap(),
+        }
+    }
+
+    /// Returns `false` if the file was already there and nothing was downloaded.
+    async fn download_with_retry(
+        &self,
+        download: &Download,
+        counter: &ProgressCounter,
+    ) -> Result<bool, (DownloadError, usize)> {
+        if let Some(sha1) = &download.sha1 {
+            if check_file_sha1(&download.file, sha1).await {
+                return Ok(false);
+            }
+        }
+        let parent = match download.file.parent() {
+            Some(parent) => parent,
+            None => return Err((DownloadError::Io("bad download path".to_string()), 0)),
+        };
+        if let Err(error) = fs::create_dir_all(parent).await {
+            return Err((error.into(), 0));
+        }
+
+        let temp_file = temp_file_path(&download.file);
+        // a leftover of an earlier run can only be trusted if we are able to verify the result
+        if download.sha1.is_none() {
+            let _ = fs::remove_file(&temp_file).await;
+        }
+        let mut attempts = 0;
+        let mut last_error = DownloadError::Network("no source available".to_string());
+        for url in self.network_options.candidates(&download.url) {
+            let mut tries = 0;
+            let mut delay = self.retry_delay;
+            loop {
+                attempts += 1;
+                tries += 1;
+                let error = match self.fetch_to(&url, download, &temp_file, counter).await {
+                    Ok(()) => match fs::rename(&temp_file, &download.file).await {
+                        Ok(()) => return Ok(true),
+                        Err(error) => error.into(),
+                    },
+                    Err(error) => error,
+                };
+                if let DownloadError::Sha1Mismatch { .. } = error {
+                    let _ = fs::remove_file(&temp_file).await;
+                }
+                if tries > self.retries || !error.is_retryable() {
+                    // the next source starts from scratch
+                    let _ = fs::remove_file(&temp_file).await;
+                    last_error = error;
+                    break;
+                }
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+        }
+        Err((last_error, attempts))
+    }
+
+    /// Download into `temp_file`, continuing from its current length if the server allows it.
+    async fn fetch_to(
+        &self,
+        url: &str,
+        download: &Download,
+        temp_file: &Path,
+        counter: &ProgressCounter,
+    ) -> Result<(), DownloadError> {
+        let mut existing = match fs::metadata(temp_file).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+        let mut response = loop {
+            let mut request = HTTP_CLIENT.get(url);
+            if existing > 0 {
+                request = request.header(header::RANGE, format!("bytes={existing}-"));
+            }
+            let response = request.send().await?;
+            if response.status() == StatusCode::RANGE_NOT_SATISFIABLE && existing > 0 {
+                // the leftover is at least as large as the file, it is probably broken, so
+                // start over without it
+                fs::remove_file(temp_file).await?;
+                existing = 0;
+                continue;
+            }
+            break response;
+        };
+        let resumed = response.status() == StatusCode::PARTIAL_CONTENT
+            && content_range_start(&response) == Some(existing);
+        if !response.status().is_success() {
+            return Err(DownloadError::Status(response.status().as_u16()));
+        }
+        let mut file = if resumed {
+            fs::OpenOptions::new().append(true).open(temp_file).await?
+        } else {
+            fs::File::create(temp_file).await?
+        };
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            counter
+                .downloaded_bytes
+                .fetch_add(chunk.len() as u64, Ordering::SeqCst);
+            (self.on_progress)(counter.snapshot());
+        }
+        file.flush().await?;
+        file.sync_all().await?;
+
+        if let Some(sha1) = &download.sha1 {
+            let actual = file_sha1(temp_file)
+                .await
+                .map_err(|error| DownloadError::Io(error.to_string()))?;
+            if !actual.eq_ignore_ascii_case(sha1) {
+                return Err(DownloadError::Sha1Mismatch {
+                    expected: sha1.clone(),
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Download a single file.
+pub async fn download(download: Download) -> Result<()> {
+    Downloader::default()
+        .download_all(vec![download])
+        .await
+        .into_result()?;
+    Ok(())
+}
+
+/// Check whether the file exists and its sha1 is the expected one.
+pub async fn check_file_sha1<P: AsRef<Path>>(path: P, sha1: &str) -> bool {
+    match file_sha1(path).await {
+        Ok(actual) => actual.eq_ignore_ascii_case(sha1),
+        Err(_) => false,
+    }
+}
+
+/// Calculate the sha1 of a file, as a lowercase hex string.
+pub async fn file_sha1<P: AsRef<Path>>(path: P) -> Result<String> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = Sha1::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Parse the start offset of `Content-Range: bytes <start>-<end>/<size>`.
+fn content_range_start(response: &reqwest::Response) -> Option<u64> {
+    let range = response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    range
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn temp_file_path(file: &Path) -> PathBuf {
+    let mut file_name = file.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".download");
+    file.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::AtomicUsize;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::core::source::DownloadSource;
+    use crate::utils::test_server::{self, Response};
+
+    /// A local HTTP stand-in. It serves `body` for every request and supports `Range`.
+    ///
+    /// The first `broken` responses are cut off in the middle of the body.
+    async fn serve(body: &'static [u8], broken: usize) -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let requests_clone = requests.clone();
+        let base = test_server::serve(move |request| {
+            let index = requests_clone.fetch_add(1, Ordering::SeqCst);
+            let start = request
+                .header("range")
+                .and_then(|range| range.strip_prefix("bytes="))
+                .map(|range| range.trim_end_matches('-').parse().unwrap())
+                .unwrap_or(0);
+            if start >= body.len() {
+                return Response::new(416, Vec::new());
+            }
+            // the broken responses end in the middle of the body
+            let end = if index < broken {
+                start + (body.len() - start) / 2
+            } else {
+                body.len()
+            };
+            let response = Response::new(if start > 0 { 206 } else { 200 }, &body[start..end])
+                .header("Content-Length", body.len() - start);
+            if start > 0 {
+                response.header(
+                    "Content-Range",
+                    format!("bytes {start}-{}/{}", body.len() - 1, body.len()),
+                )
+            } else {
+                response
+            }
+        })
+        .await;
+        (base, requests)
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("aml-download-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn body_sha1(body: &[u8]) -> String {
+        format!("{:x}", Sha1::digest(body))
+    }
+
+    const BODY: &[u8] = b"hello minecraft";
+
+    #[tokio::test]
+    async fn download_and_verify() {
+        let (url, _) = serve(BODY, 0).await;
+        let dir = temp_dir();
+        let downloads = (0..8)
+            .map(|i| Download {
+                url: format!("{url}/{i}"),
+                file: dir.join(format!("{i}/file.bin")),
+                sha1: Some(body_sha1(BODY)),
+            })
+            .collect::<Vec<_>>();
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let reported_clone = reported.clone();
+        let downloader =
+            Downloader::default()
+                .concurrency(3)
+                .on_progress(Box::new(move |progress| {
+                    reported_clone.lock().unwrap().push(progress)
+                }));
+
+        let progress = downloader
+            .download_all(downloads.clone())
+            .await
+            .into_result()
+            .unwrap();
+        assert_eq!(progress.completed, 8);
+        assert_eq!(progress.skipped, 0);
+        assert_eq!(progress.downloaded_bytes, 8 * BODY.len() as u64);
+        assert_eq!(reported.lock().unwrap().last(), Some(&progress));
+        for download in &downloads {
+            assert_eq!(std::fs::read(&download.file).unwrap(), BODY);
+            assert!(!temp_file_path(&download.file).exists());
+        }
+
+        // everything is already there
+        let report = downloader.download_all(downloads).await;
+        assert_eq!(report.progress.skipped, 8);
+        assert_eq!(report.progress.downloaded_bytes, 0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn resume_after_dropped_connection() {
+        let body: &'static [u8] = Box::leak(vec![7u8; 64 * 1024].into_boxed_slice());
+        let (url, requests) = serve(body, 2).await;
+        let dir = temp_dir();
+        let file = dir.join("client.jar");
+        let report = Downloader::default()
+            .retry_delay(Duration::from_millis(1))
+            .download_all(vec![Download {
+                url,
+                file: file.clone(),
+                sha1: Some(body_sha1(body)),
+            }])
+            .await;
+        assert!(report.is_success(), "{:?}", report.failed);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        // the retries only asked for the missing part
+        assert_eq!(report.progress.downloaded_bytes, body.len() as u64);
+        assert_eq!(std::fs::read(&file).unwrap(), body);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn restart_after_full_length_leftover() {
+        let (url, requests) = serve(BODY, 0).await;
+        let dir = temp_dir();
+        let file = dir.join("file.bin");
+        std::fs::create_dir_all(&dir).unwrap();
+        // an interrupted run wrote the whole body, but it doesn't match
+        std::fs::write(temp_file_path(&file), vec![0u8; BODY.len()]).unwrap();
+        let report = Downloader::default()
+            .retries(0)
+            .download_all(vec![Download {
+                url,
+                file: file.clone(),
+                sha1: Some(body_sha1(BODY)),
+            }])
+            .await;
+        assert!(report.is_success(), "{:?}", report.failed);
+        // the 416 answer and the request without `Range`
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert_eq!(std::fs::read(&file).unwrap(), BODY);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn report_failed_downloads() {
+        let (url, requests) = serve(BODY, usize::MAX).await;
+        let dir = temp_dir();
+        let broken = Download {
+            url: format!("{url}/broken"),
+            file: dir.join("broken.bin"),
+            sha1: Some("0".repeat(40)),
+        };
+        let report = Downloader::default()
+            .retries(2)
+            .retry_delay(Duration::from_millis(1))
+            .download_all(vec![broken.clone()])
+            .await;
+        assert_eq!(report.progress.failed, 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].download, broken);
+        assert_eq!(report.failed[0].attempts, 3);
+        assert!(matches!(report.failed[0].error, DownloadError::Network(_)));
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        assert!(!broken.file.exists());
+        assert!(!temp_file_path(&broken.file).exists());
+        assert!(report.into_result().is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn fall_back_to_next_source() {
+        let (url, requests) = serve(BODY, 0).await;
+        let dead = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead_url = format!("http://{}", dead.local_addr().unwrap());
+        drop(dead);
+        let dir = temp_dir();
+        let download = Download {
+            url: "https://files.example.com/file.bin".to_string(),
+            file: dir.join("file.bin"),
+            sha1: Some(body_sha1(BODY)),
+        };
+        let report = Downloader::default()
+            .network_options(NetworkOptions {
+                sources: vec![
+                    DownloadSource::new("dead").replace("https://files.example.com", &dead_url),
+                    DownloadSource::new("alive").replace("https://files.example.com", &url),
+                ],
+            })
+            .retries(1)
+            .retry_delay(Duration::from_millis(1))
+            .download_all(vec![download.clone()])
+            .await;
+        assert!(report.is_success(), "{:?}", report.failed);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert_eq!(std::fs::read(&download.file).unwrap(), BODY);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reject_sha1_mismatch() {
+        let (url, _) = serve(BODY, 0).await;
+        let dir = temp_dir();
+        let file = dir.join("file.bin");
+        let report = Downloader::default()
+            .retries(1)
+            .retry_delay(Duration::from_millis(1))
+            .download_all(vec![Download {
+                url,
+                file: file.clone(),
+                sha1: Some("0".repeat(40)),
+            }])
+            .await;
+        assert!(matches!(
+            report.failed[0].error,
+            DownloadError::Sha1Mismatch { .. }
+        ));
+        assert!(!file.exists());
+        assert!(!temp_file_path(&file).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,53 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use regex::Regex;
+
+/// The versions in `maven-metadata.xml` of an artifact, in the order of the document.
+pub(crate) fn metadata_versions(xml: &str) -> Vec<String> {
+    let regex = Regex::new(r"<version>\s*([^<\s]+)\s*</version>").unwrap();
+    regex
+        .captures_iter(xml)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_metadata_versions() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata>
+  <groupId>net.neoforged</groupId>
+  <artifactId>neoforge</artifactId>
+  <versioning>
+    <latest>20.4.80</latest>
+    <release>20.4.80</release>
+    <versions>
+      <version>20.2.3-beta</version>
+      <version> 20.4.80 </version>
+    </versions>
+    <lastUpdated>20240101000000</lastUpdated>
+  </versioning>
+</metadata>"#;
+        assert_eq!(metadata_versions(xml), vec!["20.2.3-beta", "20.4.80"]);
+        assert!(metadata_versions("<metadata/>").is_empty());
+    }
+}
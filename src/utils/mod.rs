@@ -16,5 +16,9 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod download;
+pub(crate) mod maven;
 pub mod nbt;
+#[cfg(test)]
+pub(crate) mod test_server;
 pub mod unzip;
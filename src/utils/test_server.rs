@@ -0,0 +1,150 @@
+/*
+ * Amethyst Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A local HTTP stand-in for the tests. Every connection carries one request and is closed
+//! after the response.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use sha1::{Digest, Sha1};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+/// The files to serve by their path, they can be added after the server started.
+pub(crate) type Files = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+pub(crate) struct Request {
+    pub(crate) path: String,
+    /// The headers with lowercase names.
+    headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Request {
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.headers
+            .iter()
+            .find(|(header, _)| *header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub(crate) struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub(crate) fn new<B: Into<Vec<u8>>>(status: u16, body: B) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Add a header. A `Content-Length` set here replaces the length of the body, which
+    /// cuts the response off when it is longer.
+    pub(crate) fn header<V: ToString>(mut self, name: &str, value: V) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Answer every request with `handler`, returns the base URL of the server.
+pub(crate) async fn serve<F>(handler: F) -> String
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let handler = Arc::new(handler);
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let mut stream = BufReader::new(stream);
+                let mut request_line = String::new();
+                stream.read_line(&mut request_line).await.unwrap();
+                let mut headers = Vec::new();
+                let mut line = String::new();
+                while stream.read_line(&mut line).await.unwrap() > 2 {
+                    if let Some((name, value)) = line.split_once(':') {
+                        headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+                    }
+                    line.clear();
+                }
+                let mut request = Request {
+                    path: request_line
+                        .split(' ')
+                        .nth(1)
+                        .unwrap_or_default()
+                        .to_string(),
+                    headers,
+                    body: Vec::new(),
+                };
+                if let Some(length) = request.header("content-length") {
+                    request.body = vec![0; length.parse().unwrap()];
+                    stream.read_exact(&mut request.body).await.unwrap();
+                }
+
+                let response = handler(request);
+                let mut head = format!("HTTP/1.1 {} Status\r\n", response.status);
+                if !response
+                    .headers
+                    .iter()
+                    .any(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                {
+                    head.push_str(&format!("Content-Length: {}\r\n", response.body.len()));
+                }
+                for (name, value) in &response.headers {
+                    head.push_str(&format!("{name}: {value}\r\n"));
+                }
+                head.push_str("Connection: close\r\n\r\n");
+                let stream = stream.get_mut();
+                stream.write_all(head.as_bytes()).await.unwrap();
+                stream.write_all(&response.body).await.unwrap();
+                stream.flush().await.unwrap();
+            });
+        }
+    });
+    format!("http://{address}")
+}
+
+/// Serve the files by their path, and `404` for the others.
+pub(crate) async fn serve_files(files: Files) -> String {
+    serve(
+        move |request| match files.lock().unwrap().get(&request.path) {
+            Some(body) => Response::new(200, body.clone()),
+            None => Response::new(404, Vec::new()),
+        },
+    )
+    .await
+}
+
+pub(crate) fn sha1(data: &[u8]) -> String {
+    format!("{:x}", Sha1::digest(data))
+}
@@ -55,10 +55,7 @@ impl Entry {
             version_name_by: zip_file.version_made_by(),
             name: zip_file.name().to_string(),
             mangled_name: zip_file.mangled_name().to_path_buf(),
-            enclosed_name: match zip_file.enclosed_name() {
-                None => None,
-                Some(value) => Some(value.to_path_buf()),
-            },
+            enclosed_name: zip_file.enclosed_name().map(|value| value.to_path_buf()),
             comment: zip_file.comment().to_string(),
             compression: zip_file.compression(),
             compressed_size: zip_file.compressed_size(),
@@ -158,31 +155,42 @@ pub async fn decompression_files<R: Read + io::Seek>(
     }
 }
 
+/// Extract all the entries of the archive to `to`, except the entries starting with one of
+/// `exclude`, like `META-INF/`.
+///
+/// Returns the paths of the extracted files, relative to `to`.
 pub fn decompression_all<R: Read + io::Seek, S: AsRef<OsStr> + ?Sized>(
     zip_archive: &mut ZipArchive<R>,
     to: &S,
-) -> Result<()> {
+    exclude: &[String],
+) -> Result<Vec<PathBuf>> {
     let to = Path::new(to).to_path_buf();
+    let mut extracted = Vec::new();
     for i in 0..zip_archive.len() {
-        let mut zip_file = zip_archive.by_index(i).unwrap();
-        let name = zip_file.name().to_string();
-        let entry = Entry::from_zip_file(&mut zip_file);
+        let mut zip_file = zip_archive.by_index(i)?;
+        if exclude
+            .iter()
+            .any(|prefix| zip_file.name().starts_with(prefix.as_str()))
+        {
+            continue;
+        }
+        // skip the entries that would be extracted outside of `to`
+        let name = match zip_file.enclosed_name() {
+            Some(name) => name.to_path_buf(),
+            None => continue,
+        };
         let path = to.join(&name);
         if zip_file.is_dir() {
-            std::fs::create_dir_all(zip_file.name()).unwrap();
+            std::fs::create_dir_all(&path)?;
             continue;
         }
         std::fs::create_dir_all(
             path.parent()
                 .ok_or(std::io::Error::from(std::io::ErrorKind::NotFound))?,
         )?;
-        std::fs::write(path, entry.content).unwrap();
-        // for entry in entries {
-        //     let entry = entry.to_string();
-        //     if name == entry {
-        //         resolved_entries.insert(entry, Entry::from_zip_file(&mut zip_file));
-        //     }
-        // }
+        let mut file = File::create(&path)?;
+        io::copy(&mut zip_file, &mut file)?;
+        extracted.push(name);
     }
-    Ok(())
+    Ok(extracted)
 }